@@ -7,11 +7,12 @@ use crate::{
     rpc_response::{
         Response, RpcAccount, RpcBlockhashFeeCalculator, RpcConfirmedBlock, RpcContactInfo,
         RpcEpochInfo, RpcFeeCalculator, RpcFeeRateGovernor, RpcIdentity, RpcKeyedAccount,
-        RpcLeaderSchedule, RpcResult, RpcVersionInfo, RpcVoteAccountStatus,
+        RpcLeaderSchedule, RpcResult, RpcTokenAmount, RpcVersionInfo, RpcVoteAccountStatus,
     },
 };
 use bincode::serialize;
 use log::*;
+use reqwest::header::HeaderMap;
 use serde_json::{json, Value};
 use solana_sdk::{
     account::Account,
@@ -44,6 +45,17 @@ impl RpcClient {
         }
     }
 
+    /// Send `headers` with every request, such as the API key of an authenticated RPC gateway
+    pub fn new_with_headers(url: String, headers: HeaderMap) -> Self {
+        Self {
+            client: Box::new(RpcClientRequest::new_with_timeout_and_headers(
+                url,
+                Duration::from_secs(20),
+                headers,
+            )),
+        }
+    }
+
     pub fn new_mock(url: String) -> Self {
         Self {
             client: Box::new(MockRpcClientRequest::new(url)),
@@ -574,6 +586,56 @@ impl RpcClient {
             .map_err(|err| ClientError::new_with_command(err.into(), "GetBalance"))
     }
 
+    /// Request the total supply of the token mint `mint`.
+    pub fn get_token_supply(&self, mint: &Pubkey) -> ClientResult<RpcTokenAmount> {
+        Ok(self
+            .get_token_supply_with_commitment(mint, CommitmentConfig::default())?
+            .value)
+    }
+
+    pub fn get_token_supply_with_commitment(
+        &self,
+        mint: &Pubkey,
+        commitment_config: CommitmentConfig,
+    ) -> RpcResult<RpcTokenAmount> {
+        let response = self
+            .client
+            .send(
+                &RpcRequest::GetTokenSupply,
+                json!([mint.to_string(), commitment_config]),
+                0,
+            )
+            .map_err(|err| err.into_with_command("GetTokenSupply"))?;
+
+        serde_json::from_value::<Response<RpcTokenAmount>>(response)
+            .map_err(|err| ClientError::new_with_command(err.into(), "GetTokenSupply"))
+    }
+
+    /// Request the token balance of the token account `pubkey`.
+    pub fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<RpcTokenAmount> {
+        Ok(self
+            .get_token_account_balance_with_commitment(pubkey, CommitmentConfig::default())?
+            .value)
+    }
+
+    pub fn get_token_account_balance_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        commitment_config: CommitmentConfig,
+    ) -> RpcResult<RpcTokenAmount> {
+        let response = self
+            .client
+            .send(
+                &RpcRequest::GetTokenAccountBalance,
+                json!([pubkey.to_string(), commitment_config]),
+                0,
+            )
+            .map_err(|err| err.into_with_command("GetTokenAccountBalance"))?;
+
+        serde_json::from_value::<Response<RpcTokenAmount>>(response)
+            .map_err(|err| ClientError::new_with_command(err.into(), "GetTokenAccountBalance"))
+    }
+
     pub fn get_program_accounts(&self, pubkey: &Pubkey) -> ClientResult<Vec<(Pubkey, Account)>> {
         let response = self
             .client
@@ -742,6 +804,24 @@ impl RpcClient {
         Ok(hash)
     }
 
+    pub fn get_health(&self) -> ClientResult<()> {
+        let response = self
+            .client
+            .send(&RpcRequest::GetHealth, Value::Null, 0)
+            .map_err(|err| err.into_with_command("GetHealth"))?;
+
+        let health = serde_json::from_value::<String>(response)
+            .map_err(|err| ClientError::new_with_command(err.into(), "GetHealth"))?;
+
+        if health != "ok" {
+            return Err(ClientError::new_with_command(
+                RpcError::RpcRequestError(format!("Unexpected health status: {}", health)).into(),
+                "GetHealth",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn poll_balance_with_timeout_and_commitment(
         &self,
         pubkey: &Pubkey,
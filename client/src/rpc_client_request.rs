@@ -4,7 +4,10 @@ use crate::{
     rpc_request::{RpcError, RpcRequest},
 };
 use log::*;
-use reqwest::{self, header::CONTENT_TYPE};
+use reqwest::{
+    self,
+    header::{HeaderMap, CONTENT_TYPE},
+};
 use solana_sdk::clock::{DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT};
 use std::{thread::sleep, time::Duration};
 
@@ -19,8 +22,19 @@ impl RpcClientRequest {
     }
 
     pub fn new_with_timeout(url: String, timeout: Duration) -> Self {
+        Self::new_with_timeout_and_headers(url, timeout, HeaderMap::new())
+    }
+
+    /// `headers` are sent with every request, such as the API key of an authenticated RPC
+    /// gateway
+    pub fn new_with_timeout_and_headers(
+        url: String,
+        timeout: Duration,
+        headers: HeaderMap,
+    ) -> Self {
         let client = reqwest::blocking::Client::builder()
             .timeout(timeout)
+            .default_headers(headers)
             .build()
             .expect("build rpc client");
 
@@ -217,6 +217,9 @@ pub struct RpcContactInfo {
     pub tpu: Option<SocketAddr>,
     /// JSON RPC port
     pub rpc: Option<SocketAddr>,
+    /// Software version of the node, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
 /// Map of leader base58 identity pubkeys to the slot indices relative to the first epoch slot
@@ -245,6 +248,19 @@ pub struct RpcVersionInfo {
     pub solana_core: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTokenAmount {
+    /// The token amount, adjusted for the mint's decimals
+    pub ui_amount: f64,
+
+    /// Number of decimals configured for the token's mint
+    pub decimals: u8,
+
+    /// The raw token amount as a string, ignoring decimals
+    pub amount: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct RpcIdentity {
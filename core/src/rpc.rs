@@ -765,7 +765,8 @@ impl RpcSol for RpcSolImpl {
                 None
             }
         }
-        let shred_version = cluster_info.my_data().shred_version;
+        let my_data = cluster_info.my_data();
+        let shred_version = my_data.shred_version;
         Ok(cluster_info
             .all_peers()
             .iter()
@@ -778,6 +779,12 @@ impl RpcSol for RpcSolImpl {
                         gossip: Some(contact_info.gossip),
                         tpu: valid_address_or_none(&contact_info.tpu),
                         rpc: valid_address_or_none(&contact_info.rpc),
+                        // Gossip doesn't carry the versions of the other nodes
+                        version: if contact_info.id == my_data.id {
+                            Some(solana_clap_utils::version!().to_string())
+                        } else {
+                            None
+                        },
                     })
                 } else {
                     None // Exclude spy nodes
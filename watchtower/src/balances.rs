@@ -0,0 +1,199 @@
+//! Checks of the balances of the monitored validators: the SOL left in their identity accounts
+//! and, with `--monitor-vote-account-withdrawals`, the withdrawals from their vote accounts
+use crate::{CheckContext, CheckResults};
+use log::*;
+use rayon::{prelude::*, ThreadPool};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::RpcClient,
+};
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+use std::{collections::HashMap, str::FromStr};
+
+/// Vote accounts only receive lamports outside of a withdrawal, so any decrease larger than this
+/// is reported
+const MAX_VOTE_ACCOUNT_BALANCE_DECREASE: u64 = 1_000_000;
+
+/// A failure to get the balance of a validator is reported once it fails this many intervals in
+/// a row, rather than on a single failed request
+const BALANCE_UNAVAILABLE_INTERVALS: usize = 3;
+
+/// What the balance checks remember of the previous intervals
+#[derive(Default)]
+pub struct BalanceChecks {
+    last_vote_account_balances: HashMap<String, u64>,
+    /// Number of consecutive intervals the balance of each validator could not be read
+    balance_failures: HashMap<String, usize>,
+}
+
+impl BalanceChecks {
+    /// Check the balances of the `monitored_identities` validators
+    pub fn check(
+        &mut self,
+        context: &CheckContext,
+        monitored_identities: &[String],
+        results: &mut CheckResults,
+    ) {
+        self.check_identity_balances(context, monitored_identities, results);
+        if context.config.monitor_vote_account_withdrawals {
+            self.check_vote_account_withdrawals(context, monitored_identities, results);
+        }
+    }
+
+    fn check_identity_balances(
+        &mut self,
+        context: &CheckContext,
+        monitored_identities: &[String],
+        results: &mut CheckResults,
+    ) {
+        let CheckContext {
+            config,
+            rpc_client,
+            thread_pool,
+            influx_output,
+            cluster_info,
+            ..
+        } = *context;
+        for (validator_identity, balance) in
+            get_balances(rpc_client, thread_pool, monitored_identities)
+        {
+            match balance.map(lamports_to_sol) {
+                Ok(balance) => {
+                    // Emitted every interval, for dashboards of the monitored validators
+                    let activated_stake = cluster_info
+                        .vote_accounts
+                        .as_ref()
+                        .map(|vote_accounts| {
+                            vote_accounts
+                                .current
+                                .iter()
+                                .chain(vote_accounts.delinquent.iter())
+                                .filter(|vai| vai.node_pubkey == validator_identity)
+                                .map(|vai| vai.activated_stake)
+                                .sum::<u64>()
+                        })
+                        .unwrap_or_default();
+                    submit_datapoint!(
+                        influx_output,
+                        Level::Info,
+                        "watchtower-validator",
+                        ("pubkey", validator_identity, String),
+                        ("balance", balance, f64),
+                        ("activated_stake", lamports_to_sol(activated_stake), f64)
+                    );
+                    if balance < config.minimum_validator_balance(&validator_identity) {
+                        *results.affected_validators.entry("balance").or_default() += 1;
+                        results.failures.push((
+                            "balance",
+                            format!(
+                                "{} identity account has {} SOL",
+                                config.validator_name(&validator_identity),
+                                balance
+                            ),
+                        ));
+                    }
+                }
+                Err(err) => {
+                    warn!("Failed to get balance of {}: {:?}", validator_identity, err);
+                    let failed_intervals = self
+                        .balance_failures
+                        .entry(validator_identity.clone())
+                        .or_default();
+                    *failed_intervals += 1;
+                    if *failed_intervals >= BALANCE_UNAVAILABLE_INTERVALS {
+                        *results
+                            .affected_validators
+                            .entry("balance-unavailable")
+                            .or_default() += 1;
+                        results.failures.push((
+                            "balance-unavailable",
+                            format!(
+                                "{} identity account balance unavailable for {} intervals: {}",
+                                config.validator_name(&validator_identity),
+                                failed_intervals,
+                                err
+                            ),
+                        ));
+                    }
+                    continue;
+                }
+            }
+            self.balance_failures.remove(&validator_identity);
+        }
+        self.balance_failures
+            .retain(|validator, _| monitored_identities.contains(validator));
+    }
+
+    fn check_vote_account_withdrawals(
+        &mut self,
+        context: &CheckContext,
+        monitored_identities: &[String],
+        results: &mut CheckResults,
+    ) {
+        let vote_accounts = match &context.cluster_info.vote_accounts {
+            Ok(vote_accounts) => vote_accounts,
+            Err(_) => return,
+        };
+        let vote_pubkeys: Vec<_> = vote_accounts
+            .current
+            .iter()
+            .chain(vote_accounts.delinquent.iter())
+            .filter(|vote_account| monitored_identities.contains(&vote_account.node_pubkey))
+            .map(|vote_account| vote_account.vote_pubkey.clone())
+            .collect();
+        for (vote_pubkey, balance) in
+            get_balances(context.rpc_client, context.thread_pool, &vote_pubkeys)
+        {
+            match balance {
+                Ok(balance) => {
+                    if let Some(last_balance) = self
+                        .last_vote_account_balances
+                        .insert(vote_pubkey.clone(), balance)
+                    {
+                        if last_balance.saturating_sub(balance) > MAX_VOTE_ACCOUNT_BALANCE_DECREASE
+                        {
+                            results.failures.push((
+                                "vote-account-withdrawal",
+                                format!(
+                                    "{} SOL withdrawn from vote account {}, {} SOL remaining",
+                                    lamports_to_sol(last_balance - balance),
+                                    vote_pubkey,
+                                    lamports_to_sol(balance)
+                                ),
+                            ));
+                        }
+                    }
+                }
+                Err(err) => warn!(
+                    "Failed to get balance of vote account {}: {:?}",
+                    vote_pubkey, err
+                ),
+            }
+        }
+    }
+}
+
+fn get_balances(
+    rpc_client: &RpcClient,
+    thread_pool: &ThreadPool,
+    pubkeys: &[String],
+) -> Vec<(String, ClientResult<u64>)> {
+    thread_pool.install(|| {
+        pubkeys
+            .par_iter()
+            .map(|pubkey| {
+                // Identities learned from the cluster are only parsed here, a malformed one is
+                // reported rather than replaced by the default pubkey
+                let balance = Pubkey::from_str(pubkey)
+                    .map_err(|err| {
+                        ClientError::from(ClientErrorKind::Custom(format!(
+                            "Invalid pubkey {}: {}",
+                            pubkey, err
+                        )))
+                    })
+                    .and_then(|pubkey| rpc_client.get_balance(&pubkey));
+                (pubkey.clone(), balance)
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,100 @@
+//! Watchtower settings that may be placed in the `--config` file alongside the cli settings
+use crate::notifier::NotificationsConfig;
+use log::*;
+use serde_derive::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io,
+};
+
+/// Names of the sanity checks that can be individually enabled or disabled
+pub const CHECK_NAMES: &[&str] = &[
+    "transaction-count",
+    "tx-count-regression",
+    "recent-blockhash",
+    "current-stake",
+    "delinquent",
+    "delinquent-stake",
+    "balance",
+    "finalization-lag",
+    "rpc-health",
+    "token-supply",
+    "token-account-balance",
+    "data-unavailable",
+    "identity-rotated",
+    "slot-stall",
+    "validator-departed",
+    "validator-count-drop",
+    "epoch-schedule",
+    "commission-too-high",
+    "peer-heartbeat",
+    "validator-version-behind",
+    "vote-account-withdrawal",
+    "stake-deactivating",
+    "stake-deactivated",
+    "vote-credits",
+    "leader-slots",
+    "slow-check",
+    "wrong-cluster",
+    "slow-block-production",
+    "root-lag",
+    "commission-changed",
+    "stake-drop",
+    "balance-unavailable",
+    "cluster-version",
+    "epoch-stall",
+    "superminority-low",
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WatchtowerConfig {
+    /// Enabled state of each sanity check by name.  Checks that are not listed remain enabled
+    pub checks: HashMap<String, bool>,
+    /// Notification backends, overridden by their environment variables
+    pub notifications: NotificationsConfig,
+    /// Overrides of the command-line thresholds, which a reload of the config file re-applies
+    pub thresholds: ThresholdsConfig,
+}
+
+/// Thresholds that override their command-line flag when present
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    /// `--interval`, such as `60s` or `5m`
+    pub interval: Option<String>,
+    /// `--active-stake-threshold`
+    pub active_stake_threshold: Option<u64>,
+    /// `--minimum-validator-balance`, in SOL
+    pub minimum_validator_balance: Option<f64>,
+    /// `--max-delinquent-validators`
+    pub max_delinquent_validators: Option<usize>,
+    /// `--max-delinquent-stake-percent`
+    pub max_delinquent_stake_percent: Option<f64>,
+}
+
+impl WatchtowerConfig {
+    pub fn load(config_file: &str) -> Result<Self, io::Error> {
+        let file = File::open(config_file.to_string())?;
+        let config = serde_yaml::from_reader(file)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+        Ok(config)
+    }
+
+    pub fn disabled_checks(&self) -> HashSet<String> {
+        self.checks
+            .iter()
+            .filter_map(|(name, enabled)| {
+                if !CHECK_NAMES.contains(&name.as_str()) {
+                    warn!("Ignoring unknown check in config file: {}", name);
+                    None
+                } else if *enabled {
+                    None
+                } else {
+                    Some(name.clone())
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,95 @@
+//! Generates a Grafana dashboard for the Prometheus metrics exposed by the watchtower
+use serde_json::{json, Value};
+
+/// Prometheus metric names, shared with the metrics endpoint
+pub const CURRENT_STAKE_PERCENT: &str = "watchtower_current_stake_percent";
+pub const DELINQUENT_VALIDATORS: &str = "watchtower_delinquent_validators";
+pub const SANITY_OK: &str = "watchtower_sanity_ok";
+pub const CYCLE_DURATION_SECONDS: &str = "watchtower_cycle_duration_seconds";
+pub const NOTIFICATIONS_SENT: &str = "watchtower_notifications_sent_total";
+pub const NOTIFICATIONS_SUPPRESSED: &str = "watchtower_notifications_suppressed_total";
+
+const PANEL_WIDTH: u64 = 12;
+const PANEL_HEIGHT: u64 = 8;
+
+fn panel(id: u64, title: &str, datasource: &str, expr: &str, legend_format: &str) -> Value {
+    // Lay the panels out two per row
+    let index = id - 1;
+    json!({
+        "id": id,
+        "type": "graph",
+        "title": title,
+        "datasource": datasource,
+        "gridPos": {
+            "x": (index % 2) * PANEL_WIDTH,
+            "y": (index / 2) * PANEL_HEIGHT,
+            "w": PANEL_WIDTH,
+            "h": PANEL_HEIGHT,
+        },
+        "targets": [{
+            "expr": expr,
+            "legendFormat": legend_format,
+            "refId": "A",
+        }],
+        "lines": true,
+        "linewidth": 1,
+        "fill": 1,
+        "legend": { "show": true },
+    })
+}
+
+/// Returns the dashboard JSON, with every panel querying the Prometheus data source named
+/// `datasource`
+pub fn generate_dashboard(datasource: &str) -> Value {
+    let panels = vec![
+        panel(
+            1,
+            "Current stake %",
+            datasource,
+            CURRENT_STAKE_PERCENT,
+            "{{instance}}",
+        ),
+        panel(
+            2,
+            "Delinquent validators",
+            datasource,
+            DELINQUENT_VALIDATORS,
+            "{{instance}}",
+        ),
+        panel(3, "Sanity ok", datasource, SANITY_OK, "{{instance}}"),
+        panel(
+            4,
+            "Cycle duration (seconds)",
+            datasource,
+            CYCLE_DURATION_SECONDS,
+            "{{instance}}",
+        ),
+        panel(
+            5,
+            "Notifications sent per minute",
+            datasource,
+            &format!("sum by (channel) (rate({}[5m])) * 60", NOTIFICATIONS_SENT),
+            "{{channel}}",
+        ),
+        panel(
+            6,
+            "Notifications suppressed per minute",
+            datasource,
+            &format!(
+                "sum by (test) (rate({}[5m])) * 60",
+                NOTIFICATIONS_SUPPRESSED
+            ),
+            "{{test}}",
+        ),
+    ];
+    json!({
+        "title": "Solana Watchtower",
+        "uid": "solana-watchtower",
+        "tags": ["solana", "watchtower"],
+        "timezone": "browser",
+        "schemaVersion": 22,
+        "refresh": "1m",
+        "time": { "from": "now-6h", "to": "now" },
+        "panels": panels,
+    })
+}
@@ -0,0 +1,101 @@
+//! A minimal HTTP server reporting whether the main loop is still making progress, used by peer
+//! watchtower instances to check each other's liveness and by orchestrators as a liveness probe
+use log::*;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread::{self, Builder},
+    time::{Duration, Instant},
+};
+
+pub const HEARTBEAT_PATH: &str = "/heartbeat";
+/// Same as the heartbeat, at the conventional path of a liveness probe
+pub const HEALTHZ_PATH: &str = "/healthz";
+
+pub struct HealthServer {
+    last_iteration: Arc<Mutex<Instant>>,
+    max_iteration_age: Arc<Mutex<Duration>>,
+}
+
+impl HealthServer {
+    /// Start serving on `addr`.  The main loop is considered stalled when an iteration has not
+    /// completed within `max_iteration_age`
+    pub fn start(addr: &SocketAddr, max_iteration_age: Duration) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let last_iteration = Arc::new(Mutex::new(Instant::now()));
+        let thread_last_iteration = last_iteration.clone();
+        let max_iteration_age = Arc::new(Mutex::new(max_iteration_age));
+        let thread_max_iteration_age = max_iteration_age.clone();
+        Builder::new()
+            .name("watchtower-health".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let last_iteration = thread_last_iteration.clone();
+                            let max_iteration_age = thread_max_iteration_age.clone();
+                            // Serve each request on its own thread so a slow client can't block
+                            // the others
+                            thread::spawn(move || {
+                                if let Err(err) =
+                                    handle_request(stream, &last_iteration, &max_iteration_age)
+                                {
+                                    debug!("Health request failed: {}", err);
+                                }
+                            });
+                        }
+                        Err(err) => warn!("Failed to accept health connection: {}", err),
+                    }
+                }
+            })?;
+        info!("Health endpoint listening on {}", addr);
+        Ok(Self {
+            last_iteration,
+            max_iteration_age,
+        })
+    }
+
+    /// Change how long an iteration may take, when a reload of the config file changes the
+    /// interval
+    pub fn set_max_iteration_age(&self, max_iteration_age: Duration) {
+        *self.max_iteration_age.lock().unwrap() = max_iteration_age;
+    }
+
+    /// Record that the main loop completed an iteration
+    pub fn iteration_completed(&self) {
+        *self.last_iteration.lock().unwrap() = Instant::now();
+    }
+}
+
+fn handle_request(
+    stream: TcpStream,
+    last_iteration: &Mutex<Instant>,
+    max_iteration_age: &Mutex<Duration>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, body) = if path == HEARTBEAT_PATH || path == HEALTHZ_PATH {
+        let iteration_age = last_iteration.lock().unwrap().elapsed();
+        if iteration_age <= *max_iteration_age.lock().unwrap() {
+            ("200 OK", "ok".to_string())
+        } else {
+            (
+                "503 Service Unavailable",
+                format!("no iteration completed in {:?}", iteration_age),
+            )
+        }
+    } else {
+        ("404 Not Found", "not found".to_string())
+    };
+    write!(
+        &stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
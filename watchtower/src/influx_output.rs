@@ -0,0 +1,115 @@
+//! Writes the watchtower data points in InfluxDB line protocol to a file or UDP socket, in
+//! addition to submitting them to the `solana_metrics` host
+use log::*;
+use solana_metrics::datapoint::DataPoint;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    net::{SocketAddr, UdpSocket},
+    path::PathBuf,
+};
+
+/// Submit a data point to both the `solana_metrics` host and the `--influx-output`, if any
+macro_rules! submit_datapoint {
+    ($influx_output:expr, $level:expr, $name:expr, $($fields:tt)+) => {
+        $influx_output.submit(
+            solana_metrics::create_datapoint!(@point $name, $($fields)+),
+            $level,
+        )
+    };
+}
+
+enum Target {
+    File(File),
+    Udp(UdpSocket, SocketAddr),
+}
+
+pub struct InfluxOutput {
+    target: Option<Target>,
+    /// Tag set appended to every measurement name, including the leading comma
+    tags: String,
+    /// The `--cluster` label, added as a field of the `solana_metrics` data points since they
+    /// carry no tags of their own
+    cluster: Option<String>,
+}
+
+/// Escape a tag key or value as required by the line protocol
+fn escape_tag(tag: &str) -> String {
+    tag.replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+impl InfluxOutput {
+    /// `output` is either a UDP `ADDRESS:PORT` or the path of a file to append to
+    pub fn new(
+        output: Option<&str>,
+        cluster: &str,
+        instance: &str,
+        cluster_label: Option<&str>,
+    ) -> io::Result<Self> {
+        let target = match output {
+            None => None,
+            Some(output) => Some(match output.parse::<SocketAddr>() {
+                Ok(addr) => {
+                    let bind_addr = if addr.is_ipv4() {
+                        "0.0.0.0:0"
+                    } else {
+                        "[::]:0"
+                    };
+                    Target::Udp(UdpSocket::bind(bind_addr)?, addr)
+                }
+                Err(_) => Target::File(
+                    OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(PathBuf::from(output))?,
+                ),
+            }),
+        };
+        Ok(Self {
+            target,
+            tags: format!(
+                ",cluster={},instance={}",
+                escape_tag(cluster),
+                escape_tag(instance)
+            ),
+            cluster: cluster_label.map(|cluster_label| cluster_label.to_string()),
+        })
+    }
+
+    fn line(&self, point: &DataPoint) -> String {
+        let fields: Vec<_> = point
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        format!(
+            "{}{} {} {}\n",
+            escape_tag(point.name),
+            self.tags,
+            fields.join(","),
+            // DataPoint timestamps are in milliseconds, the line protocol default is nanoseconds
+            point.timestamp * 1_000_000
+        )
+    }
+
+    pub fn submit(&self, mut point: DataPoint, level: Level) {
+        if let Some(target) = &self.target {
+            let line = self.line(&point);
+            let result = match target {
+                Target::File(file) => (&*file).write_all(line.as_bytes()),
+                Target::Udp(socket, addr) => socket.send_to(line.as_bytes(), addr).map(|_| ()),
+            };
+            if let Err(err) = result {
+                warn!("Failed to write to the influx output: {}", err);
+            }
+        }
+        if log_enabled!(level) {
+            if let Some(cluster) = &self.cluster {
+                point.add_field_str("cluster", cluster);
+            }
+            solana_metrics::submit(point, level);
+        }
+    }
+}
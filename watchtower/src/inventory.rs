@@ -0,0 +1,71 @@
+//! Tracks the set of current validators for `--monitor-all-current`, optionally persisted to an
+//! inventory file with one validator identity per line
+use log::*;
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub struct Inventory {
+    validators: BTreeSet<String>,
+    inventory_file: Option<PathBuf>,
+}
+
+fn load_inventory_file(inventory_file: &Path) -> BTreeSet<String> {
+    match fs::read_to_string(inventory_file) {
+        Ok(contents) => contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to read {}: {}", inventory_file.display(), err);
+            }
+            BTreeSet::new()
+        }
+    }
+}
+
+impl Inventory {
+    pub fn new(inventory_file: Option<PathBuf>) -> Self {
+        let validators = inventory_file
+            .as_deref()
+            .map(load_inventory_file)
+            .unwrap_or_default();
+        Self {
+            validators,
+            inventory_file,
+        }
+    }
+
+    pub fn validators(&self) -> &BTreeSet<String> {
+        &self.validators
+    }
+
+    /// Replace the inventory with `current`, returning the validators that joined and departed
+    pub fn update(&mut self, current: BTreeSet<String>) -> (Vec<String>, Vec<String>) {
+        let joined: Vec<_> = current.difference(&self.validators).cloned().collect();
+        let departed: Vec<_> = self.validators.difference(&current).cloned().collect();
+        if !joined.is_empty() || !departed.is_empty() {
+            self.validators = current;
+            self.save();
+        }
+        (joined, departed)
+    }
+
+    fn save(&self) {
+        if let Some(inventory_file) = &self.inventory_file {
+            let contents: String = self
+                .validators
+                .iter()
+                .map(|validator| format!("{}\n", validator))
+                .collect();
+            if let Err(err) = fs::write(inventory_file, contents) {
+                warn!("Failed to write {}: {}", inventory_file.display(), err);
+            }
+        }
+    }
+}
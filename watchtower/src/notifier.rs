@@ -1,13 +1,284 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use lettre::{
+    smtp::{authentication::Credentials, ClientSecurity, SmtpClient},
+    ClientTlsParameters, Transport,
+};
+use lettre_email::EmailBuilder;
 use log::*;
+use native_tls::TlsConnector;
 use reqwest::blocking::Client;
-use serde_json::json;
-use std::env;
+#[cfg(feature = "sns")]
+use rusoto_core::Region;
+#[cfg(feature = "sns")]
+use rusoto_sns::{PublishInput, Sns, SnsClient};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+#[cfg(feature = "systemd")]
+use std::os::unix::net::UnixDatagram;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env, fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    time::Duration,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+/// Describes what a notification is about, for backends that track incidents
+#[derive(Clone, Copy, Debug)]
+pub enum NotificationType<'a> {
+    /// The sanity test named `test` is failing
+    Trigger { test: &'a str, severity: Severity },
+    /// All sanity failures have cleared
+    Resolve,
+    /// An informational event that does not affect the failure state
+    Info,
+    /// A `--test-notification` to check the configuration of the backends
+    Test,
+}
+
+/// Which of the configured channels a notification goes to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channels {
+    All,
+    /// The channels that relay the message to people: chat, email, SMS, webhooks and logs
+    Messages,
+    /// The backends that track incidents: PagerDuty, Opsgenie and Statuspage
+    Incidents,
+}
+
+impl Channels {
+    fn includes(self, channel: &str) -> bool {
+        let tracks_incidents = ["PagerDuty", "Opsgenie", "Statuspage"].contains(&channel);
+        match self {
+            Channels::All => true,
+            Channels::Messages => !tracks_incidents,
+            Channels::Incidents => tracks_incidents,
+        }
+    }
+}
+
+/// The incidents that the incident tracking backends have open, persisted in the `--state-file`
+/// so that the all clear after a restart still resolves them
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct OpenIncidents {
+    /// PagerDuty dedup keys
+    pub pagerduty: BTreeSet<String>,
+    /// Opsgenie aliases
+    pub opsgenie: BTreeSet<String>,
+    /// statuspage.io incident id of each failing sanity test
+    pub statuspage: BTreeMap<String, String>,
+}
+
+/// Log a failed delivery to `channel`.  It is metered by the caller of `Notifier::send`, from
+/// the delivery results
+fn delivery_failed(channel: &str, action: &str, err: &reqwest::Error) {
+    if err.is_timeout() {
+        warn!("Timed out trying to {} via {}", action, channel);
+    } else {
+        warn!("Failed to {} via {}: {:?}", action, channel, err);
+    }
+}
+
+/// Slack message for a notification, prefixed with an emoji for its severity
+fn slack_payload(msg: &str, notification_type: &NotificationType) -> Value {
+    let emoji = match notification_type {
+        NotificationType::Trigger {
+            severity: Severity::Critical,
+            ..
+        } => Some(":rotating_light:"),
+        NotificationType::Trigger {
+            severity: Severity::Warning,
+            ..
+        } => Some(":warning:"),
+        NotificationType::Resolve => Some(":white_check_mark:"),
+        NotificationType::Info | NotificationType::Test => None,
+    };
+    match emoji {
+        Some(emoji) => json!({ "text": format!("{} {}", emoji, msg) }),
+        None => json!({ "text": msg }),
+    }
+}
+
+/// Microsoft Teams MessageCard for a notification, titled after the failing test and colored by
+/// its severity
+fn teams_payload(
+    notification_prefix: &str,
+    msg: &str,
+    notification_type: &NotificationType,
+) -> Value {
+    let (title, theme_color) = match notification_type {
+        NotificationType::Trigger { test, severity } => (
+            format!("{} {}", notification_prefix, test),
+            match severity {
+                Severity::Critical => "FF0000",
+                Severity::Warning => "FFA500",
+            },
+        ),
+        NotificationType::Resolve => (format!("{} All clear", notification_prefix), "2EB886"),
+        NotificationType::Info => (format!("{} Notice", notification_prefix), "0076D7"),
+        NotificationType::Test => (format!("{} Test", notification_prefix), "0076D7"),
+    };
+    json!({
+        "@type": "MessageCard",
+        "@context": "https://schema.org/extensions",
+        "summary": title,
+        "title": title,
+        "themeColor": theme_color,
+        "text": msg,
+    })
+}
+
+/// Discord rejects messages longer than this many characters
+const DISCORD_MAX_MESSAGE_LEN: usize = 2000;
+
+/// Truncate `msg` to at most `max_len` characters, marking the truncation with a trailing `…`
+fn truncate_message(msg: &str, max_len: usize) -> String {
+    if msg.chars().count() <= max_len {
+        msg.to_string()
+    } else {
+        let mut truncated: String = msg.chars().take(max_len - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
 
 struct TelegramWebHook {
+    api_url: String,
     bot_token: String,
     chat_id: String,
 }
 
+impl TelegramWebHook {
+    /// Both the bot token and the chat id are required, a partial configuration disables
+    /// Telegram notifications
+    fn new(bot_token: Option<String>, chat_id: Option<String>) -> Option<Self> {
+        match (bot_token, chat_id) {
+            (Some(bot_token), Some(chat_id)) => Some(Self {
+                api_url: TELEGRAM_API_URL.to_string(),
+                bot_token,
+                chat_id,
+            }),
+            (Some(_), None) => {
+                warn!("TELEGRAM_CHAT_ID is not set, Telegram notifications disabled");
+                None
+            }
+            (None, Some(_)) => {
+                warn!("TELEGRAM_BOT_TOKEN is not set, Telegram notifications disabled");
+                None
+            }
+            (None, None) => {
+                info!("Telegram notifications disabled");
+                None
+            }
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/bot{}/sendMessage", self.api_url, self.bot_token)
+    }
+
+    fn payload(&self, msg: &str) -> Value {
+        json!({ "chat_id": self.chat_id, "text": msg })
+    }
+}
+
+/// The notification backends to enable and their targets, from the `notifications` section of
+/// the `--config` file.  A backend is enabled when all of its settings are present
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub discord_webhook: Option<String>,
+    pub slack_webhook: Option<String>,
+    pub teams_webhook: Option<String>,
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    /// In the `TWILIO_CONFIG` format
+    pub twilio_config: Option<String>,
+    pub pagerduty_integration_key: Option<String>,
+    pub opsgenie_api_key: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<String>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// Comma-separated recipient addresses
+    pub smtp_to: Option<String>,
+    /// Sender address, defaults to the SMTP username
+    pub smtp_from: Option<String>,
+    pub webhook_url: Option<String>,
+    /// JSON payload with `{{message}}`, `{{severity}}` and `{{test}}` placeholders
+    pub webhook_template: Option<String>,
+    /// Comma-separated `NAME=VALUE` headers
+    pub webhook_headers: Option<String>,
+    /// File or named pipe to append the notifications to
+    pub notify_log_file: Option<String>,
+    /// `text` or `json`
+    pub notify_log_format: Option<String>,
+    #[cfg(feature = "sns")]
+    pub sns_topic_arn: Option<String>,
+    #[cfg(feature = "statuspage")]
+    pub statuspage_api_key: Option<String>,
+    #[cfg(feature = "statuspage")]
+    pub statuspage_page_id: Option<String>,
+}
+
+impl NotificationsConfig {
+    /// Override the settings with the environment variables that are set, so that secrets such
+    /// as tokens can be kept out of the config file
+    pub fn with_env_overrides(mut self) -> Self {
+        #[cfg_attr(not(any(feature = "sns", feature = "statuspage")), allow(unused_mut))]
+        let mut overrides = vec![
+            ("DISCORD_WEBHOOK", &mut self.discord_webhook),
+            ("SLACK_WEBHOOK", &mut self.slack_webhook),
+            ("TEAMS_WEBHOOK", &mut self.teams_webhook),
+            ("TELEGRAM_BOT_TOKEN", &mut self.telegram_bot_token),
+            ("TELEGRAM_CHAT_ID", &mut self.telegram_chat_id),
+            ("TWILIO_CONFIG", &mut self.twilio_config),
+            (
+                "PAGERDUTY_INTEGRATION_KEY",
+                &mut self.pagerduty_integration_key,
+            ),
+            ("OPSGENIE_API_KEY", &mut self.opsgenie_api_key),
+            ("SMTP_HOST", &mut self.smtp_host),
+            ("SMTP_PORT", &mut self.smtp_port),
+            ("SMTP_USERNAME", &mut self.smtp_username),
+            ("SMTP_PASSWORD", &mut self.smtp_password),
+            ("SMTP_TO", &mut self.smtp_to),
+            ("SMTP_FROM", &mut self.smtp_from),
+            ("WEBHOOK_URL", &mut self.webhook_url),
+            ("WEBHOOK_TEMPLATE", &mut self.webhook_template),
+            ("WEBHOOK_HEADERS", &mut self.webhook_headers),
+            ("NOTIFY_LOG_FILE", &mut self.notify_log_file),
+            ("NOTIFY_LOG_FORMAT", &mut self.notify_log_format),
+        ];
+        #[cfg(feature = "sns")]
+        overrides.push(("SNS_TOPIC_ARN", &mut self.sns_topic_arn));
+        #[cfg(feature = "statuspage")]
+        overrides.extend(vec![
+            ("STATUSPAGE_API_KEY", &mut self.statuspage_api_key),
+            ("STATUSPAGE_PAGE_ID", &mut self.statuspage_page_id),
+        ]);
+        for (name, setting) in overrides {
+            if let Ok(value) = env::var(name) {
+                *setting = Some(value);
+            }
+        }
+        self
+    }
+}
+
 #[derive(Debug, Default)]
 struct TwilioWebHook {
     account: String,
@@ -25,17 +296,18 @@ impl TwilioWebHook {
     }
 }
 
-fn get_twilio_config() -> Result<Option<TwilioWebHook>, String> {
-    let config_var = env::var("TWILIO_CONFIG");
-
-    if config_var.is_err() {
-        info!("Twilio notifications disabled");
-        return Ok(None);
-    }
+fn get_twilio_config(config_var: Option<&str>) -> Result<Option<TwilioWebHook>, String> {
+    let config_var = match config_var {
+        Some(config_var) => config_var,
+        None => {
+            info!("Twilio notifications disabled");
+            return Ok(None);
+        }
+    };
 
     let mut config = TwilioWebHook::default();
 
-    for pair in config_var.unwrap().split(',') {
+    for pair in config_var.split(',') {
         let nv: Vec<_> = pair.split('=').collect();
         if nv.len() != 2 {
             return Err(format!("TWILIO_CONFIG is invalid: '{}'", pair));
@@ -56,68 +328,1159 @@ fn get_twilio_config() -> Result<Option<TwilioWebHook>, String> {
     Ok(Some(config))
 }
 
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// Triggers a PagerDuty incident per failing sanity test through the Events API v2, resolving
+/// them on the all clear
+struct PagerDutyWebHook {
+    events_url: String,
+    integration_key: String,
+    /// Dedup keys of the incidents that have been triggered and not yet resolved
+    triggered: RefCell<HashSet<String>>,
+}
+
+impl PagerDutyWebHook {
+    fn new(integration_key: String) -> Self {
+        Self {
+            events_url: PAGERDUTY_EVENTS_URL.to_string(),
+            integration_key,
+            triggered: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Repeated failures of the same test share a dedup key so that they are grouped into one
+    /// incident
+    fn dedup_key(test: &str) -> String {
+        format!("solana-watchtower-{}", test)
+    }
+
+    /// Returns the events to send for a notification
+    fn events(&self, msg: &str, notification_type: &NotificationType) -> Vec<Value> {
+        let mut triggered = self.triggered.borrow_mut();
+        match notification_type {
+            NotificationType::Trigger { test, severity } => {
+                let dedup_key = Self::dedup_key(test);
+                triggered.insert(dedup_key.clone());
+                vec![json!({
+                    "routing_key": self.integration_key,
+                    "event_action": "trigger",
+                    "dedup_key": dedup_key,
+                    "payload": {
+                        "summary": msg,
+                        "source": "solana-watchtower",
+                        "severity": match severity {
+                            Severity::Warning => "warning",
+                            Severity::Critical => "critical",
+                        },
+                    },
+                })]
+            }
+            NotificationType::Resolve => triggered
+                .drain()
+                .map(|dedup_key| {
+                    json!({
+                        "routing_key": self.integration_key,
+                        "event_action": "resolve",
+                        "dedup_key": dedup_key,
+                    })
+                })
+                .collect(),
+            NotificationType::Info => vec![],
+            // Exercise both actions, resolving the test incident right away
+            NotificationType::Test => {
+                let dedup_key = Self::dedup_key("test-notification");
+                vec![
+                    json!({
+                        "routing_key": self.integration_key,
+                        "event_action": "trigger",
+                        "dedup_key": dedup_key,
+                        "payload": {
+                            "summary": msg,
+                            "source": "solana-watchtower",
+                            "severity": "info",
+                        },
+                    }),
+                    json!({
+                        "routing_key": self.integration_key,
+                        "event_action": "resolve",
+                        "dedup_key": dedup_key,
+                    }),
+                ]
+            }
+        }
+    }
+
+    /// Returns whether every event was sent, or None if there are no events to send
+    fn send(
+        &self,
+        client: &Client,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Option<bool> {
+        let events = self.events(msg, notification_type);
+        if events.is_empty() {
+            return None;
+        }
+        let mut delivered = true;
+        for event in events {
+            if let Err(err) = client
+                .post(&self.events_url)
+                .json(&event)
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("PagerDuty", "send event", &err);
+                delivered = false;
+            }
+        }
+        Some(delivered)
+    }
+}
+
+const OPSGENIE_API_URL: &str = "https://api.opsgenie.com";
+
+/// Opsgenie rejects alert messages longer than this many characters
+const OPSGENIE_MAX_MESSAGE_LEN: usize = 130;
+
+/// Creates an Opsgenie alert per failing sanity test through the Alert API, closing them on the
+/// all clear
+struct OpsgenieWebHook {
+    api_url: String,
+    api_key: String,
+    /// Aliases of the alerts that have been created and not yet closed
+    opened: RefCell<HashSet<String>>,
+}
+
+impl OpsgenieWebHook {
+    fn new(api_key: String) -> Self {
+        Self {
+            api_url: OPSGENIE_API_URL.to_string(),
+            api_key,
+            opened: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Repeated failures of the same test share an alias so that they are deduplicated into one
+    /// alert
+    fn alias(test: &str) -> String {
+        format!("solana-watchtower-{}", test)
+    }
+
+    fn create_request(&self, msg: &str, alias: &str, priority: &str) -> (String, Value) {
+        (
+            format!("{}/v2/alerts", self.api_url),
+            json!({
+                "message": truncate_message(msg, OPSGENIE_MAX_MESSAGE_LEN),
+                "description": msg,
+                "alias": alias,
+                "priority": priority,
+                "source": "solana-watchtower",
+            }),
+        )
+    }
+
+    fn close_request(&self, msg: &str, alias: &str) -> (String, Value) {
+        (
+            format!(
+                "{}/v2/alerts/{}/close?identifierType=alias",
+                self.api_url, alias
+            ),
+            json!({ "source": "solana-watchtower", "note": msg }),
+        )
+    }
+
+    /// Returns the url and body of each request to send for a notification
+    fn requests(&self, msg: &str, notification_type: &NotificationType) -> Vec<(String, Value)> {
+        let mut opened = self.opened.borrow_mut();
+        match notification_type {
+            NotificationType::Trigger { test, severity } => {
+                let alias = Self::alias(test);
+                let priority = match severity {
+                    Severity::Warning => "P3",
+                    Severity::Critical => "P1",
+                };
+                let request = self.create_request(msg, &alias, priority);
+                opened.insert(alias);
+                vec![request]
+            }
+            NotificationType::Resolve => opened
+                .drain()
+                .map(|alias| self.close_request(msg, &alias))
+                .collect(),
+            NotificationType::Info => vec![],
+            // Exercise both requests, closing the test alert right away
+            NotificationType::Test => {
+                let alias = Self::alias("test-notification");
+                vec![
+                    self.create_request(msg, &alias, "P5"),
+                    self.close_request(msg, &alias),
+                ]
+            }
+        }
+    }
+
+    /// Returns whether every request succeeded, or None if there are no requests to send
+    fn send(
+        &self,
+        client: &Client,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Option<bool> {
+        let requests = self.requests(msg, notification_type);
+        if requests.is_empty() {
+            return None;
+        }
+        let mut delivered = true;
+        for (url, body) in requests {
+            if let Err(err) = client
+                .post(&url)
+                .header("Authorization", format!("GenieKey {}", self.api_key))
+                .json(&body)
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Opsgenie", "send alert", &err);
+                delivered = false;
+            }
+        }
+        Some(delivered)
+    }
+}
+
+const DEFAULT_WEBHOOK_TEMPLATE: &str =
+    r#"{"message": "{{message}}", "severity": "{{severity}}", "test": "{{test}}"}"#;
+
+/// POSTs notifications to an arbitrary endpoint, with a JSON payload rendered from a template
+struct GenericWebHook {
+    url: String,
+    template: Value,
+    headers: Vec<(String, String)>,
+}
+
+impl GenericWebHook {
+    fn new(config: &NotificationsConfig) -> Option<Self> {
+        let url = match &config.webhook_url {
+            Some(url) => url.clone(),
+            None => {
+                info!("Webhook notifications disabled");
+                return None;
+            }
+        };
+        let template = config
+            .webhook_template
+            .as_deref()
+            .unwrap_or(DEFAULT_WEBHOOK_TEMPLATE);
+        let template = match serde_json::from_str(template) {
+            Ok(template) => template,
+            Err(err) => {
+                warn!(
+                    "WEBHOOK_TEMPLATE is not valid JSON: {}, webhook notifications disabled",
+                    err
+                );
+                return None;
+            }
+        };
+        let mut headers = vec![];
+        for header in config
+            .webhook_headers
+            .iter()
+            .flat_map(|headers| headers.split(','))
+        {
+            let nv: Vec<_> = header.splitn(2, '=').map(str::trim).collect();
+            if nv.len() != 2 || nv[0].is_empty() {
+                warn!(
+                    "WEBHOOK_HEADERS is invalid: '{}', webhook notifications disabled",
+                    header
+                );
+                return None;
+            }
+            headers.push((nv[0].to_string(), nv[1].to_string()));
+        }
+        Some(Self {
+            url,
+            template,
+            headers,
+        })
+    }
+
+    /// Fill in the placeholders of the string values of the template.  The values are substituted
+    /// after parsing so that they never need to be escaped
+    fn render(template: &Value, placeholders: &[(&str, &str)]) -> Value {
+        match template {
+            Value::String(value) => Value::String(
+                placeholders
+                    .iter()
+                    .fold(value.clone(), |value, (placeholder, replacement)| {
+                        value.replace(placeholder, replacement)
+                    }),
+            ),
+            Value::Array(values) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| Self::render(value, placeholders))
+                    .collect(),
+            ),
+            Value::Object(values) => Value::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Self::render(value, placeholders)))
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    fn payload(&self, msg: &str, notification_type: &NotificationType) -> Value {
+        let (severity, test) = match notification_type {
+            NotificationType::Trigger {
+                test,
+                severity: Severity::Critical,
+            } => ("critical", *test),
+            NotificationType::Trigger {
+                test,
+                severity: Severity::Warning,
+            } => ("warning", *test),
+            NotificationType::Resolve | NotificationType::Info | NotificationType::Test => {
+                ("info", "")
+            }
+        };
+        Self::render(
+            &self.template,
+            &[
+                ("{{message}}", msg),
+                ("{{severity}}", severity),
+                ("{{test}}", test),
+            ],
+        )
+    }
+}
+
+/// The SMTP submission port, which expects STARTTLS
+const SMTP_DEFAULT_PORT: u16 = 587;
+
+/// Emails notifications through an SMTP server, upgrading the connection with STARTTLS
+struct SmtpNotifier {
+    host: String,
+    port: u16,
+    username: String,
+    password: String,
+    from: String,
+    to: Vec<String>,
+    timeout: Duration,
+    /// Starts the subject of each email, the `--notification-prefix`
+    notification_prefix: String,
+}
+
+impl SmtpNotifier {
+    /// The host, username, password and recipients are required, a partial configuration
+    /// disables email notifications
+    fn new(
+        config: &NotificationsConfig,
+        timeout: Duration,
+        notification_prefix: &str,
+    ) -> Option<Self> {
+        let (host, username, password, to) = match (
+            &config.smtp_host,
+            &config.smtp_username,
+            &config.smtp_password,
+            &config.smtp_to,
+        ) {
+            (None, None, None, None) => {
+                info!("Email notifications disabled");
+                return None;
+            }
+            (Some(host), Some(username), Some(password), Some(to)) => {
+                (host, username, password, to)
+            }
+            _ => {
+                warn!(
+                    "SMTP_HOST, SMTP_USERNAME, SMTP_PASSWORD and SMTP_TO are required, \
+                     email notifications disabled"
+                );
+                return None;
+            }
+        };
+        let port = match &config.smtp_port {
+            Some(port) => match port.parse() {
+                Ok(port) => port,
+                Err(err) => {
+                    warn!(
+                        "SMTP_PORT is invalid: {}, email notifications disabled",
+                        err
+                    );
+                    return None;
+                }
+            },
+            None => SMTP_DEFAULT_PORT,
+        };
+        let to: Vec<_> = to
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(str::to_string)
+            .collect();
+        if to.is_empty() {
+            warn!("SMTP_TO has no recipients, email notifications disabled");
+            return None;
+        }
+
+        Some(Self {
+            host: host.clone(),
+            port,
+            username: username.clone(),
+            password: password.clone(),
+            from: config.smtp_from.clone().unwrap_or_else(|| username.clone()),
+            to,
+            timeout,
+            notification_prefix: notification_prefix.to_string(),
+        })
+    }
+
+    fn subject(notification_prefix: &str, notification_type: &NotificationType) -> String {
+        match notification_type {
+            NotificationType::Trigger { test, .. } => {
+                format!("{} {} failed", notification_prefix, test)
+            }
+            NotificationType::Resolve => format!("{} All clear", notification_prefix),
+            NotificationType::Info => format!("{} Notice", notification_prefix),
+            NotificationType::Test => format!("{} Test notification", notification_prefix),
+        }
+    }
+
+    fn try_send(
+        &self,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut email = EmailBuilder::new()
+            .from(self.from.as_str())
+            .subject(Self::subject(&self.notification_prefix, notification_type))
+            .text(msg);
+        for to in &self.to {
+            email = email.to(to.as_str());
+        }
+        let email = email.build()?;
+
+        let tls_parameters = ClientTlsParameters::new(self.host.clone(), TlsConnector::new()?);
+        let mut transport = SmtpClient::new(
+            (self.host.as_str(), self.port),
+            ClientSecurity::Required(tls_parameters),
+        )?
+        .credentials(Credentials::new(
+            self.username.clone(),
+            self.password.clone(),
+        ))
+        .timeout(Some(self.timeout))
+        .transport();
+        let result = transport.send(email.into());
+        transport.close();
+        result?;
+        Ok(())
+    }
+
+    /// Returns false if the email failed to send
+    fn send(&self, msg: &str, notification_type: &NotificationType) -> bool {
+        match self.try_send(msg, notification_type) {
+            Ok(()) => true,
+            Err(err) => {
+                email_failed(&err);
+                false
+            }
+        }
+    }
+}
+
+/// Log a failed email delivery, the SMTP counterpart of `delivery_failed`
+fn email_failed(err: &dyn fmt::Display) {
+    warn!("Failed to send email via SMTP: {}", err);
+}
+
+/// The region of an SNS topic, from its `arn:aws:sns:REGION:ACCOUNT:NAME` ARN
+#[cfg(feature = "sns")]
+fn sns_topic_region(topic_arn: &str) -> Option<Region> {
+    let parts: Vec<_> = topic_arn.split(':').collect();
+    match parts.as_slice() {
+        ["arn", _, "sns", region, _, _] => region.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Publishes notifications to an SNS topic, with the AWS credentials of the environment, the
+/// AWS config files or the instance profile
+#[cfg(feature = "sns")]
+struct SnsNotifier {
+    client: SnsClient,
+    topic_arn: String,
+    timeout: Duration,
+    /// Starts the subject of each message, the `--notification-prefix`
+    notification_prefix: String,
+}
+
+#[cfg(feature = "sns")]
+impl SnsNotifier {
+    fn new(
+        topic_arn: Option<String>,
+        timeout: Duration,
+        notification_prefix: &str,
+    ) -> Option<Self> {
+        let topic_arn = match topic_arn {
+            Some(topic_arn) => topic_arn,
+            None => {
+                info!("SNS notifications disabled");
+                return None;
+            }
+        };
+        match sns_topic_region(&topic_arn) {
+            Some(region) => Some(Self {
+                client: SnsClient::new(region),
+                topic_arn,
+                timeout,
+                notification_prefix: notification_prefix.to_string(),
+            }),
+            None => {
+                warn!(
+                    "SNS_TOPIC_ARN {} is invalid, SNS notifications disabled",
+                    topic_arn
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns false if the message failed to publish
+    fn send(&self, msg: &str, notification_type: &NotificationType) -> bool {
+        let input = PublishInput {
+            topic_arn: Some(self.topic_arn.clone()),
+            // Used by the email subscriptions of the topic
+            subject: Some(SmtpNotifier::subject(
+                &self.notification_prefix,
+                notification_type,
+            )),
+            message: msg.to_string(),
+            ..PublishInput::default()
+        };
+        match self.client.publish(input).with_timeout(self.timeout).sync() {
+            Ok(_) => true,
+            Err(err) => {
+                warn!("Failed to publish message via SNS: {}", err);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(feature = "statuspage")]
+const STATUSPAGE_API_URL: &str = "https://api.statuspage.io/v1";
+
+/// Opens a statuspage.io incident per failing sanity test, updating it while the test keeps
+/// failing and resolving it on the all clear
+#[cfg(feature = "statuspage")]
+struct StatuspageWebHook {
+    api_url: String,
+    api_key: String,
+    page_id: String,
+    /// Starts the name of each incident, the `--notification-prefix`
+    notification_prefix: String,
+    /// Open incident id for each failing sanity test
+    incidents: RefCell<HashMap<String, String>>,
+}
+
+#[cfg(feature = "statuspage")]
+impl StatuspageWebHook {
+    fn new(api_key: String, page_id: String, notification_prefix: &str) -> Self {
+        Self {
+            api_url: STATUSPAGE_API_URL.to_string(),
+            api_key,
+            page_id,
+            notification_prefix: notification_prefix.to_string(),
+            incidents: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn incident_impact(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Warning => "minor",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Returns whether every request succeeded, or None if there is nothing to send
+    fn send(
+        &self,
+        client: &Client,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Option<bool> {
+        let url = format!("{}/pages/{}/incidents", self.api_url, self.page_id);
+        let authorization = format!("OAuth {}", self.api_key);
+        let mut incidents = self.incidents.borrow_mut();
+
+        match notification_type {
+            NotificationType::Trigger { test, severity } => {
+                if let Some(incident_id) = incidents.get(*test) {
+                    let data = json!({ "incident": { "body": msg } });
+                    if let Err(err) = client
+                        .patch(&format!("{}/{}", url, incident_id))
+                        .header("Authorization", &authorization)
+                        .json(&data)
+                        .send()
+                        .and_then(|response| response.error_for_status())
+                    {
+                        delivery_failed("Statuspage", "update incident", &err);
+                        return Some(false);
+                    }
+                    return Some(true);
+                }
+
+                let data = json!({
+                    "incident": {
+                        "name": format!("{} {}", self.notification_prefix, test),
+                        "status": "investigating",
+                        "impact_override": Self::incident_impact(*severity),
+                        "body": msg,
+                    }
+                });
+                match client
+                    .post(&url)
+                    .header("Authorization", &authorization)
+                    .json(&data)
+                    .send()
+                    .and_then(|response| response.error_for_status())
+                    .and_then(|response| response.json::<serde_json::Value>())
+                {
+                    Ok(incident) => {
+                        if let Some(incident_id) = incident["id"].as_str() {
+                            incidents.insert(test.to_string(), incident_id.to_string());
+                        }
+                    }
+                    Err(err) => {
+                        delivery_failed("Statuspage", "create incident", &err);
+                        return Some(false);
+                    }
+                }
+                Some(true)
+            }
+            NotificationType::Info => None,
+            // Opening an incident would publish the test on the status page, only check that the
+            // page can be read with the API key
+            NotificationType::Test => {
+                if let Err(err) = client
+                    .get(&format!("{}/pages/{}", self.api_url, self.page_id))
+                    .header("Authorization", &authorization)
+                    .send()
+                    .and_then(|response| response.error_for_status())
+                {
+                    delivery_failed("Statuspage", "read page", &err);
+                    return Some(false);
+                }
+                Some(true)
+            }
+            NotificationType::Resolve if incidents.is_empty() => None,
+            NotificationType::Resolve => {
+                let mut delivered = true;
+                for (_, incident_id) in incidents.drain() {
+                    let data = json!({ "incident": { "status": "resolved", "body": msg } });
+                    if let Err(err) = client
+                        .patch(&format!("{}/{}", url, incident_id))
+                        .header("Authorization", &authorization)
+                        .json(&data)
+                        .send()
+                        .and_then(|response| response.error_for_status())
+                    {
+                        delivery_failed("Statuspage", "resolve incident", &err);
+                        delivered = false;
+                    }
+                }
+                Some(delivered)
+            }
+        }
+    }
+}
+
+/// Appends each notification as a timestamped line to a file or named pipe, for environments
+/// where a local agent ships the alerts rather than a webhook
+struct LogFileNotifier {
+    path: PathBuf,
+    json: bool,
+}
+
+impl LogFileNotifier {
+    fn new(config: &NotificationsConfig) -> Option<Self> {
+        let path = match &config.notify_log_file {
+            Some(path) => PathBuf::from(path),
+            None => {
+                info!("Log file notifications disabled");
+                return None;
+            }
+        };
+        let json = match config.notify_log_format.as_deref() {
+            None | Some("text") => false,
+            Some("json") => true,
+            Some(format) => {
+                warn!(
+                    "NOTIFY_LOG_FORMAT {} is invalid, expected text or json, log file \
+                     notifications disabled",
+                    format
+                );
+                return None;
+            }
+        };
+        Some(Self { path, json })
+    }
+
+    fn line(&self, msg: &str, notification_type: &NotificationType, now: DateTime<Utc>) -> String {
+        let timestamp = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let (kind, test) = match notification_type {
+            NotificationType::Trigger {
+                test,
+                severity: Severity::Critical,
+            } => ("critical", Some(*test)),
+            NotificationType::Trigger {
+                test,
+                severity: Severity::Warning,
+            } => ("warning", Some(*test)),
+            NotificationType::Resolve => ("resolve", None),
+            NotificationType::Info => ("info", None),
+            NotificationType::Test => ("test", None),
+        };
+        if self.json {
+            format!(
+                "{}\n",
+                json!({
+                    "timestamp": timestamp,
+                    "type": kind,
+                    "test": test,
+                    "message": msg,
+                })
+            )
+        } else {
+            // One line per notification, whatever the number of failures in it
+            format!("{} {} {}\n", timestamp, kind, msg.replace('\n', " | "))
+        }
+    }
+
+    /// Returns false if the line failed to write
+    fn send(&self, msg: &str, notification_type: &NotificationType) -> bool {
+        let line = self.line(msg, notification_type, Utc::now());
+        // Opened for every notification so that a rotated or deleted file is recreated
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        // Don't block on a named pipe that has no reader
+        #[cfg(unix)]
+        options.custom_flags(libc::O_NONBLOCK);
+        if let Err(err) = options
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+        {
+            warn!("Failed to write to {}: {}", self.path.display(), err);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(feature = "systemd")]
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Writes notifications to the systemd journal using its native protocol, with the failing test
+/// and severity in the `WT_TEST` and `WT_SEVERITY` fields
+#[cfg(feature = "systemd")]
+struct JournalNotifier {
+    socket: UnixDatagram,
+}
+
+#[cfg(feature = "systemd")]
+impl JournalNotifier {
+    fn new() -> std::io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNAL_SOCKET)?;
+        Ok(Self { socket })
+    }
+
+    fn append_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+        entry.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Multi-line values use the length-prefixed binary form of the protocol
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+
+    /// The journal entry of a notification, in the native protocol
+    fn entry(msg: &str, notification_type: &NotificationType) -> Vec<u8> {
+        // syslog priorities: 3 = err, 4 = warning, 6 = info
+        let (priority, severity, test) = match notification_type {
+            NotificationType::Trigger {
+                test,
+                severity: Severity::Critical,
+            } => ("3", "critical", Some(test)),
+            NotificationType::Trigger {
+                test,
+                severity: Severity::Warning,
+            } => ("4", "warning", Some(test)),
+            NotificationType::Resolve | NotificationType::Info | NotificationType::Test => {
+                ("6", "info", None)
+            }
+        };
+
+        let mut entry = vec![];
+        Self::append_field(&mut entry, "MESSAGE", msg);
+        Self::append_field(&mut entry, "PRIORITY", priority);
+        Self::append_field(&mut entry, "SYSLOG_IDENTIFIER", "solana-watchtower");
+        Self::append_field(&mut entry, "WT_SEVERITY", severity);
+        if let Some(test) = test {
+            Self::append_field(&mut entry, "WT_TEST", test);
+        }
+        entry
+    }
+
+    /// Returns false if the entry failed to write
+    fn send(&self, msg: &str, notification_type: &NotificationType) -> bool {
+        if let Err(err) = self.socket.send(&Self::entry(msg, notification_type)) {
+            warn!("Failed to write to the systemd journal: {:?}", err);
+            return false;
+        }
+        true
+    }
+}
+
 pub struct Notifier {
     client: Client,
     discord_webhook: Option<String>,
     slack_webhook: Option<String>,
+    teams_webhook: Option<String>,
     telegram_webhook: Option<TelegramWebHook>,
     twilio_webhook: Option<TwilioWebHook>,
+    pagerduty_webhook: Option<PagerDutyWebHook>,
+    opsgenie_webhook: Option<OpsgenieWebHook>,
+    smtp_notifier: Option<SmtpNotifier>,
+    generic_webhook: Option<GenericWebHook>,
+    log_file_notifier: Option<LogFileNotifier>,
+    #[cfg(feature = "sns")]
+    sns_notifier: Option<SnsNotifier>,
+    #[cfg(feature = "statuspage")]
+    statuspage_webhook: Option<StatuspageWebHook>,
+    #[cfg(feature = "systemd")]
+    journal_notifier: Option<JournalNotifier>,
+    /// Starts the titles of the Teams cards, the `--notification-prefix`
+    notification_prefix: String,
+    /// Number of notifications sent to each channel
+    notifications_sent: RefCell<HashMap<&'static str, u64>>,
 }
 
 impl Notifier {
-    pub fn new() -> Self {
-        let discord_webhook = env::var("DISCORD_WEBHOOK")
-            .map_err(|_| {
-                info!("Discord notifications disabled");
-            })
-            .ok();
-        let slack_webhook = env::var("SLACK_WEBHOOK")
-            .map_err(|_| {
-                info!("Slack notifications disabled");
-            })
-            .ok();
-        let telegram_webhook = if let (Ok(bot_token), Ok(chat_id)) =
-            (env::var("TELEGRAM_BOT_TOKEN"), env::var("TELEGRAM_CHAT_ID"))
-        {
-            Some(TelegramWebHook { bot_token, chat_id })
+    /// `timeout` limits how long a single notification request may take, so that a hung channel
+    /// can't stall the monitoring.  `notification_prefix` starts the titles, subjects and incident
+    /// names that the backends give notifications
+    pub fn new(config: &NotificationsConfig, timeout: Duration, notification_prefix: &str) -> Self {
+        let discord_webhook = config.discord_webhook.clone();
+        if discord_webhook.is_none() {
+            info!("Discord notifications disabled");
+        }
+        let slack_webhook = config.slack_webhook.clone();
+        if slack_webhook.is_none() {
+            info!("Slack notifications disabled");
+        }
+        let teams_webhook = config.teams_webhook.clone();
+        if teams_webhook.is_none() {
+            info!("Microsoft Teams notifications disabled");
+        }
+        let telegram_webhook = TelegramWebHook::new(
+            config.telegram_bot_token.clone(),
+            config.telegram_chat_id.clone(),
+        );
+        let twilio_webhook =
+            get_twilio_config(config.twilio_config.as_deref()).unwrap_or_else(|err| {
+                warn!("{}, Twilio notifications disabled", err);
+                None
+            });
+        let pagerduty_webhook = config
+            .pagerduty_integration_key
+            .clone()
+            .map(PagerDutyWebHook::new);
+        if pagerduty_webhook.is_none() {
+            info!("PagerDuty notifications disabled");
+        }
+        let opsgenie_webhook = config.opsgenie_api_key.clone().map(OpsgenieWebHook::new);
+        if opsgenie_webhook.is_none() {
+            info!("Opsgenie notifications disabled");
+        }
+        let smtp_notifier = SmtpNotifier::new(config, timeout, notification_prefix);
+        let generic_webhook = GenericWebHook::new(config);
+        let log_file_notifier = LogFileNotifier::new(config);
+        #[cfg(feature = "sns")]
+        let sns_notifier =
+            SnsNotifier::new(config.sns_topic_arn.clone(), timeout, notification_prefix);
+        #[cfg(feature = "statuspage")]
+        let statuspage_webhook = if let (Some(api_key), Some(page_id)) = (
+            config.statuspage_api_key.clone(),
+            config.statuspage_page_id.clone(),
+        ) {
+            Some(StatuspageWebHook::new(
+                api_key,
+                page_id,
+                notification_prefix,
+            ))
         } else {
-            info!("Telegram notifications disabled");
+            info!("Statuspage notifications disabled");
+            None
+        };
+        // systemd sets JOURNAL_STREAM for services whose output goes to the journal
+        #[cfg(feature = "systemd")]
+        let journal_notifier = if env::var("JOURNAL_STREAM").is_ok() {
+            JournalNotifier::new()
+                .map_err(|err| warn!("Failed to connect to {}: {:?}", JOURNAL_SOCKET, err))
+                .ok()
+        } else {
+            info!("systemd journal notifications disabled");
             None
         };
-        let twilio_webhook = get_twilio_config()
-            .map_err(|err| panic!("Twilio config error: {}", err))
-            .unwrap();
 
         Notifier {
-            client: Client::new(),
+            client: Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("notifier client"),
             discord_webhook,
             slack_webhook,
+            teams_webhook,
             telegram_webhook,
             twilio_webhook,
+            pagerduty_webhook,
+            opsgenie_webhook,
+            smtp_notifier,
+            generic_webhook,
+            log_file_notifier,
+            #[cfg(feature = "sns")]
+            sns_notifier,
+            #[cfg(feature = "statuspage")]
+            statuspage_webhook,
+            #[cfg(feature = "systemd")]
+            journal_notifier,
+            notification_prefix: notification_prefix.to_string(),
+            notifications_sent: RefCell::new(HashMap::new()),
         }
     }
 
-    pub fn send(&self, msg: &str) {
+    /// Names of the configured notification channels
+    pub fn channels(&self) -> Vec<&'static str> {
+        let mut channels = vec![];
+        if self.discord_webhook.is_some() {
+            channels.push("Discord");
+        }
+        if self.slack_webhook.is_some() {
+            channels.push("Slack");
+        }
+        if self.teams_webhook.is_some() {
+            channels.push("Teams");
+        }
+        if self.telegram_webhook.is_some() {
+            channels.push("Telegram");
+        }
+        if self.twilio_webhook.is_some() {
+            channels.push("Twilio");
+        }
+        if self.pagerduty_webhook.is_some() {
+            channels.push("PagerDuty");
+        }
+        if self.opsgenie_webhook.is_some() {
+            channels.push("Opsgenie");
+        }
+        if self.smtp_notifier.is_some() {
+            channels.push("Email");
+        }
+        if self.generic_webhook.is_some() {
+            channels.push("Webhook");
+        }
+        if self.log_file_notifier.is_some() {
+            channels.push("Log file");
+        }
+        #[cfg(feature = "sns")]
+        {
+            if self.sns_notifier.is_some() {
+                channels.push("SNS");
+            }
+        }
+        #[cfg(feature = "statuspage")]
+        {
+            if self.statuspage_webhook.is_some() {
+                channels.push("Statuspage");
+            }
+        }
+        #[cfg(feature = "systemd")]
+        {
+            if self.journal_notifier.is_some() {
+                channels.push("Journal");
+            }
+        }
+        channels
+    }
+
+    /// Returns the number of notifications sent to each channel so far
+    pub fn notifications_sent(&self) -> HashMap<&'static str, u64> {
+        self.notifications_sent.borrow().clone()
+    }
+
+    /// Returns the incidents that are open in the incident tracking backends
+    pub fn open_incidents(&self) -> OpenIncidents {
+        #[cfg_attr(not(feature = "statuspage"), allow(unused_mut))]
+        let mut open_incidents = OpenIncidents::default();
+        if let Some(pagerduty_webhook) = &self.pagerduty_webhook {
+            open_incidents.pagerduty = pagerduty_webhook
+                .triggered
+                .borrow()
+                .iter()
+                .cloned()
+                .collect();
+        }
+        if let Some(opsgenie_webhook) = &self.opsgenie_webhook {
+            open_incidents.opsgenie = opsgenie_webhook.opened.borrow().iter().cloned().collect();
+        }
+        #[cfg(feature = "statuspage")]
+        {
+            if let Some(statuspage_webhook) = &self.statuspage_webhook {
+                open_incidents.statuspage = statuspage_webhook
+                    .incidents
+                    .borrow()
+                    .iter()
+                    .map(|(test, incident_id)| (test.clone(), incident_id.clone()))
+                    .collect();
+            }
+        }
+        open_incidents
+    }
+
+    /// Resume tracking the incidents left open by a previous run.  Those of backends that are no
+    /// longer configured are dropped
+    pub fn restore_open_incidents(&self, open_incidents: &OpenIncidents) {
+        if let Some(pagerduty_webhook) = &self.pagerduty_webhook {
+            pagerduty_webhook
+                .triggered
+                .borrow_mut()
+                .extend(open_incidents.pagerduty.iter().cloned());
+        }
+        if let Some(opsgenie_webhook) = &self.opsgenie_webhook {
+            opsgenie_webhook
+                .opened
+                .borrow_mut()
+                .extend(open_incidents.opsgenie.iter().cloned());
+        }
+        #[cfg(feature = "statuspage")]
+        {
+            if let Some(statuspage_webhook) = &self.statuspage_webhook {
+                statuspage_webhook.incidents.borrow_mut().extend(
+                    open_incidents
+                        .statuspage
+                        .iter()
+                        .map(|(test, incident_id)| (test.clone(), incident_id.clone())),
+                );
+            }
+        }
+    }
+
+    /// Send `msg` to every configured channel, returning whether it was delivered to each of
+    /// them
+    pub fn send(
+        &self,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Vec<(&'static str, bool)> {
+        self.send_to(Channels::All, msg, notification_type)
+    }
+
+    /// Send `msg` to the configured `channels`, returning whether it was delivered to each of
+    /// them.  Channels with nothing to send for the notification are left out
+    pub fn send_to(
+        &self,
+        channels: Channels,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Vec<(&'static str, bool)> {
+        let mut results = vec![];
+        if channels != Channels::Incidents {
+            let failed = self.send_messages(msg, notification_type);
+            results.extend(
+                self.channels()
+                    .into_iter()
+                    .filter(|channel| Channels::Messages.includes(channel))
+                    .map(|channel| (channel, !failed.contains(&channel))),
+            );
+        }
+        if channels != Channels::Messages {
+            results.extend(self.send_incidents(msg, notification_type));
+        }
+
+        let mut notifications_sent = self.notifications_sent.borrow_mut();
+        for (channel, delivered) in &results {
+            if *delivered {
+                *notifications_sent.entry(channel).or_default() += 1;
+            }
+        }
+        results
+    }
+
+    /// Send `msg` to the channels that relay it to people, returning those that failed
+    fn send_messages(&self, msg: &str, notification_type: &NotificationType) -> Vec<&'static str> {
+        let mut failed = vec![];
         if let Some(webhook) = &self.discord_webhook {
-            let data = json!({ "content": msg });
-            if let Err(err) = self.client.post(webhook).json(&data).send() {
-                warn!("Failed to send Discord message: {:?}", err);
+            let data = json!({ "content": truncate_message(msg, DISCORD_MAX_MESSAGE_LEN) });
+            if let Err(err) = self
+                .client
+                .post(webhook)
+                .json(&data)
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Discord", "send message", &err);
+                failed.push("Discord");
             }
         }
 
         if let Some(webhook) = &self.slack_webhook {
-            let data = json!({ "text": msg });
-            if let Err(err) = self.client.post(webhook).json(&data).send() {
-                warn!("Failed to send Slack message: {:?}", err);
+            let data = slack_payload(msg, notification_type);
+            if let Err(err) = self
+                .client
+                .post(webhook)
+                .json(&data)
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Slack", "send message", &err);
+                failed.push("Slack");
             }
         }
 
-        if let Some(TelegramWebHook { chat_id, bot_token }) = &self.telegram_webhook {
-            let data = json!({ "chat_id": chat_id, "text": msg });
-            let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+        if let Some(webhook) = &self.teams_webhook {
+            if let Err(err) = self
+                .client
+                .post(webhook)
+                .json(&teams_payload(
+                    &self.notification_prefix,
+                    msg,
+                    notification_type,
+                ))
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Teams", "send message", &err);
+                failed.push("Teams");
+            }
+        }
 
-            if let Err(err) = self.client.post(&url).json(&data).send() {
-                warn!("Failed to send Telegram message: {:?}", err);
+        if let Some(telegram_webhook) = &self.telegram_webhook {
+            if let Err(err) = self
+                .client
+                .post(&telegram_webhook.url())
+                .json(&telegram_webhook.payload(msg))
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Telegram", "send message", &err);
+                failed.push("Telegram");
             }
         }
 
@@ -133,9 +1496,909 @@ impl Notifier {
                 account, token, account
             );
             let params = [("To", to), ("From", from), ("Body", &msg.to_string())];
-            if let Err(err) = self.client.post(&url).form(&params).send() {
-                warn!("Failed to send Twilio message: {:?}", err);
+            if let Err(err) = self
+                .client
+                .post(&url)
+                .form(&params)
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Twilio", "send message", &err);
+                failed.push("Twilio");
+            }
+        }
+
+        if let Some(smtp_notifier) = &self.smtp_notifier {
+            if !smtp_notifier.send(msg, notification_type) {
+                failed.push("Email");
+            }
+        }
+
+        if let Some(generic_webhook) = &self.generic_webhook {
+            let mut request = self
+                .client
+                .post(&generic_webhook.url)
+                .json(&generic_webhook.payload(msg, notification_type));
+            for (name, value) in &generic_webhook.headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+            if let Err(err) = request
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                delivery_failed("Webhook", "send message", &err);
+                failed.push("Webhook");
+            }
+        }
+
+        if let Some(log_file_notifier) = &self.log_file_notifier {
+            if !log_file_notifier.send(msg, notification_type) {
+                failed.push("Log file");
+            }
+        }
+
+        #[cfg(feature = "sns")]
+        {
+            if let Some(sns_notifier) = &self.sns_notifier {
+                if !sns_notifier.send(msg, notification_type) {
+                    failed.push("SNS");
+                }
+            }
+        }
+
+        #[cfg(feature = "systemd")]
+        {
+            if let Some(journal_notifier) = &self.journal_notifier {
+                if !journal_notifier.send(msg, notification_type) {
+                    failed.push("Journal");
+                }
             }
         }
+        failed
+    }
+
+    /// Send `msg` to the backends that track incidents, returning whether it was delivered to
+    /// each of those that had something to send
+    fn send_incidents(
+        &self,
+        msg: &str,
+        notification_type: &NotificationType,
+    ) -> Vec<(&'static str, bool)> {
+        let mut results = vec![];
+        if let Some(pagerduty_webhook) = &self.pagerduty_webhook {
+            results.extend(
+                pagerduty_webhook
+                    .send(&self.client, msg, notification_type)
+                    .map(|delivered| ("PagerDuty", delivered)),
+            );
+        }
+
+        if let Some(opsgenie_webhook) = &self.opsgenie_webhook {
+            results.extend(
+                opsgenie_webhook
+                    .send(&self.client, msg, notification_type)
+                    .map(|delivered| ("Opsgenie", delivered)),
+            );
+        }
+
+        #[cfg(feature = "statuspage")]
+        {
+            if let Some(statuspage_webhook) = &self.statuspage_webhook {
+                results.extend(
+                    statuspage_webhook
+                        .send(&self.client, msg, notification_type)
+                        .map(|delivered| ("Statuspage", delivered)),
+                );
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    fn test_notifier() -> Notifier {
+        Notifier {
+            client: Client::new(),
+            discord_webhook: None,
+            slack_webhook: None,
+            teams_webhook: None,
+            telegram_webhook: None,
+            twilio_webhook: None,
+            pagerduty_webhook: None,
+            opsgenie_webhook: None,
+            smtp_notifier: None,
+            generic_webhook: None,
+            log_file_notifier: None,
+            #[cfg(feature = "sns")]
+            sns_notifier: None,
+            #[cfg(feature = "statuspage")]
+            statuspage_webhook: None,
+            #[cfg(feature = "systemd")]
+            journal_notifier: None,
+            notification_prefix: "solana-watchtower:".to_string(),
+            notifications_sent: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Serve a single HTTP request with `status`, returning the endpoint url and a handle that
+    /// yields the request body
+    fn mock_endpoint(status: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                let line = line.to_lowercase();
+                if line.starts_with("content-length:") {
+                    content_length = line["content-length:".len()..].trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            write!(
+                &stream,
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        (url, handle)
+    }
+
+    /// Serve an HTTP request for each of the `responses`, a status and a body, returning the
+    /// endpoint url and a handle that yields the request line and body of each request
+    #[cfg(feature = "statuspage")]
+    fn mock_server(
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (String, JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|(status, response_body)| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(&stream);
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == "\r\n" {
+                            break;
+                        }
+                        let line = line.to_lowercase();
+                        if line.starts_with("content-length:") {
+                            content_length =
+                                line["content-length:".len()..].trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    write!(
+                        &stream,
+                        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        response_body.len(),
+                        response_body
+                    )
+                    .unwrap();
+                    (
+                        request_line.trim_end().to_string(),
+                        String::from_utf8(body).unwrap(),
+                    )
+                })
+                .collect()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_truncate_message() {
+        assert_eq!(truncate_message("short", DISCORD_MAX_MESSAGE_LEN), "short");
+
+        let msg = "x".repeat(DISCORD_MAX_MESSAGE_LEN);
+        assert_eq!(truncate_message(&msg, DISCORD_MAX_MESSAGE_LEN), msg);
+
+        let truncated = truncate_message(&"é".repeat(3000), DISCORD_MAX_MESSAGE_LEN);
+        assert_eq!(truncated.chars().count(), DISCORD_MAX_MESSAGE_LEN);
+        assert!(truncated.ends_with("é…"));
+    }
+
+    #[test]
+    fn test_discord_webhook_truncates() {
+        let (url, request) = mock_endpoint("204 No Content");
+        let mut notifier = test_notifier();
+        notifier.discord_webhook = Some(url);
+
+        notifier.send(&"x".repeat(5000), &NotificationType::Info);
+        let body: serde_json::Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        let content = body["content"].as_str().unwrap();
+        assert_eq!(content.chars().count(), DISCORD_MAX_MESSAGE_LEN);
+        assert!(content.ends_with('…'));
+    }
+
+    #[test]
+    fn test_notifier_channels() {
+        let notifier = Notifier::new(
+            &NotificationsConfig::default(),
+            Duration::from_secs(1),
+            "solana-watchtower:",
+        );
+        assert!(notifier.channels().is_empty());
+
+        let config = NotificationsConfig {
+            slack_webhook: Some("https://hooks.slack.com/services/x".to_string()),
+            // Incomplete, so Telegram stays disabled
+            telegram_bot_token: Some("token".to_string()),
+            pagerduty_integration_key: Some("key".to_string()),
+            ..NotificationsConfig::default()
+        };
+        let notifier = Notifier::new(&config, Duration::from_secs(1), "solana-watchtower:");
+        assert_eq!(notifier.channels(), vec!["Slack", "PagerDuty"]);
+    }
+
+    #[test]
+    fn test_telegram_config() {
+        let token = || Some("token".to_string());
+        let chat_id = || Some("1234".to_string());
+        assert!(TelegramWebHook::new(token(), chat_id()).is_some());
+        assert!(TelegramWebHook::new(token(), None).is_none());
+        assert!(TelegramWebHook::new(None, chat_id()).is_none());
+        assert!(TelegramWebHook::new(None, None).is_none());
+    }
+
+    #[test]
+    fn test_telegram_request() {
+        let telegram_webhook =
+            TelegramWebHook::new(Some("token".to_string()), Some("1234".to_string())).unwrap();
+        assert_eq!(
+            telegram_webhook.url(),
+            "https://api.telegram.org/bottoken/sendMessage"
+        );
+        assert_eq!(
+            telegram_webhook.payload("solana-watchtower: All clear"),
+            json!({ "chat_id": "1234", "text": "solana-watchtower: All clear" })
+        );
+    }
+
+    #[test]
+    fn test_telegram_webhook() {
+        let (url, request) = mock_endpoint("200 OK");
+        let mut telegram_webhook =
+            TelegramWebHook::new(Some("token".to_string()), Some("1234".to_string())).unwrap();
+        telegram_webhook.api_url = url;
+        let mut notifier = test_notifier();
+        notifier.telegram_webhook = Some(telegram_webhook);
+
+        notifier.send("solana-watchtower: All clear", &NotificationType::Resolve);
+        let body: Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        assert_eq!(body["chat_id"], "1234");
+        assert_eq!(body["text"], "solana-watchtower: All clear");
+    }
+
+    #[test]
+    fn test_smtp_config() {
+        let config = NotificationsConfig {
+            smtp_host: Some("smtp.example.com".to_string()),
+            smtp_username: Some("watchtower@example.com".to_string()),
+            smtp_password: Some("password".to_string()),
+            smtp_to: Some("ops@example.com, oncall@example.com".to_string()),
+            ..NotificationsConfig::default()
+        };
+        let smtp_notifier =
+            SmtpNotifier::new(&config, Duration::from_secs(1), "solana-watchtower:").unwrap();
+        assert_eq!(smtp_notifier.port, SMTP_DEFAULT_PORT);
+        assert_eq!(smtp_notifier.from, "watchtower@example.com");
+        assert_eq!(
+            smtp_notifier.to,
+            vec!["ops@example.com", "oncall@example.com"]
+        );
+
+        let incomplete_config = NotificationsConfig {
+            smtp_password: None,
+            ..config.clone()
+        };
+        assert!(SmtpNotifier::new(
+            &incomplete_config,
+            Duration::from_secs(1),
+            "solana-watchtower:"
+        )
+        .is_none());
+
+        let invalid_port_config = NotificationsConfig {
+            smtp_port: Some("smtp".to_string()),
+            ..config
+        };
+        assert!(SmtpNotifier::new(
+            &invalid_port_config,
+            Duration::from_secs(1),
+            "solana-watchtower:"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_smtp_subject() {
+        assert_eq!(
+            SmtpNotifier::subject(
+                "solana-watchtower:",
+                &NotificationType::Trigger {
+                    test: "delinquent",
+                    severity: Severity::Critical,
+                }
+            ),
+            "solana-watchtower: delinquent failed"
+        );
+        assert_eq!(
+            SmtpNotifier::subject("eu-west watchtower:", &NotificationType::Resolve),
+            "eu-west watchtower: All clear"
+        );
+    }
+
+    #[test]
+    fn test_log_file_line() {
+        let now = DateTime::parse_from_rfc3339("2020-04-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let trigger = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+        let mut log_file_notifier = LogFileNotifier {
+            path: PathBuf::from("alerts.log"),
+            json: false,
+        };
+        assert_eq!(
+            log_file_notifier.line("Error: a\nError: b", &trigger, now),
+            "2020-04-01T10:00:00Z critical Error: a | Error: b\n"
+        );
+
+        log_file_notifier.json = true;
+        let line = log_file_notifier.line("Error: a\nError: b", &trigger, now);
+        assert!(line.ends_with('\n'));
+        let line: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            line,
+            json!({
+                "timestamp": "2020-04-01T10:00:00Z",
+                "type": "critical",
+                "test": "delinquent",
+                "message": "Error: a\nError: b",
+            })
+        );
+        let line = log_file_notifier.line("All clear", &NotificationType::Resolve, now);
+        let line: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(line["type"], "resolve");
+        assert!(line["test"].is_null());
+    }
+
+    #[test]
+    fn test_log_file_notifier() {
+        let path =
+            std::env::temp_dir().join(format!("solana-watchtower-test-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut notifier = test_notifier();
+        notifier.log_file_notifier = LogFileNotifier::new(&NotificationsConfig {
+            notify_log_file: Some(path.to_str().unwrap().to_string()),
+            ..NotificationsConfig::default()
+        });
+        assert_eq!(notifier.channels(), vec!["Log file"]);
+
+        assert_eq!(
+            notifier.send("Error: a", &NotificationType::Info),
+            vec![("Log file", true)]
+        );
+        assert_eq!(
+            notifier.send("Error: b", &NotificationType::Info),
+            vec![("Log file", true)]
+        );
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with(" info Error: b"));
+
+        // A rotated file is recreated by the next notification
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            notifier.send("Error: c", &NotificationType::Info),
+            vec![("Log file", true)]
+        );
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.ends_with(" info Error: c\n"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_generic_webhook_config() {
+        let config = |template: Option<&str>, headers: Option<&str>| NotificationsConfig {
+            webhook_url: Some("https://alerts.example.com".to_string()),
+            webhook_template: template.map(str::to_string),
+            webhook_headers: headers.map(str::to_string),
+            ..NotificationsConfig::default()
+        };
+        let generic_webhook = GenericWebHook::new(&config(
+            None,
+            Some("Authorization=Bearer a=b, X-Source=sol"),
+        ))
+        .unwrap();
+        assert_eq!(
+            generic_webhook.headers,
+            vec![
+                ("Authorization".to_string(), "Bearer a=b".to_string()),
+                ("X-Source".to_string(), "sol".to_string()),
+            ]
+        );
+        assert!(GenericWebHook::new(&config(Some("{"), None)).is_none());
+        assert!(GenericWebHook::new(&config(None, Some("Authorization"))).is_none());
+        assert!(GenericWebHook::new(&NotificationsConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_generic_webhook_payload() {
+        let template = r#"{"alert": {"text": "{{test}}: {{message}}", "level": "{{severity}}"}, "tags": ["{{test}}", 1]}"#;
+        let generic_webhook = GenericWebHook::new(&NotificationsConfig {
+            webhook_url: Some("https://alerts.example.com".to_string()),
+            webhook_template: Some(template.to_string()),
+            ..NotificationsConfig::default()
+        })
+        .unwrap();
+        let trigger = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+        assert_eq!(
+            generic_webhook.payload("validator \"a\" delinquent", &trigger),
+            json!({
+                "alert": {
+                    "text": "delinquent: validator \"a\" delinquent",
+                    "level": "critical",
+                },
+                "tags": ["delinquent", 1],
+            })
+        );
+        assert_eq!(
+            generic_webhook.payload("All clear", &NotificationType::Resolve)["alert"]["level"],
+            "info"
+        );
+    }
+
+    #[test]
+    fn test_pagerduty_dedup_key() {
+        assert_eq!(
+            PagerDutyWebHook::dedup_key("delinquent"),
+            "solana-watchtower-delinquent"
+        );
+        assert_ne!(
+            PagerDutyWebHook::dedup_key("delinquent"),
+            PagerDutyWebHook::dedup_key("balance")
+        );
+    }
+
+    #[test]
+    fn test_pagerduty_resolve_after_trigger() {
+        let pagerduty_webhook = PagerDutyWebHook::new("key".to_string());
+        let trigger = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+
+        // Nothing to resolve before a trigger
+        assert!(pagerduty_webhook
+            .events("All clear", &NotificationType::Resolve)
+            .is_empty());
+        assert!(pagerduty_webhook
+            .events("info", &NotificationType::Info)
+            .is_empty());
+
+        let events = pagerduty_webhook.events("Error: delinquent", &trigger);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["event_action"], "trigger");
+        assert_eq!(events[0]["routing_key"], "key");
+        assert_eq!(events[0]["dedup_key"], "solana-watchtower-delinquent");
+        assert_eq!(events[0]["payload"]["severity"], "critical");
+        assert_eq!(events[0]["payload"]["summary"], "Error: delinquent");
+
+        // A repeated failure is sent with the same dedup key, and only resolved once
+        let events = pagerduty_webhook.events("Error: delinquent", &trigger);
+        assert_eq!(events[0]["dedup_key"], "solana-watchtower-delinquent");
+        let events = pagerduty_webhook.events("All clear", &NotificationType::Resolve);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["event_action"], "resolve");
+        assert_eq!(events[0]["dedup_key"], "solana-watchtower-delinquent");
+        assert!(pagerduty_webhook
+            .events("All clear", &NotificationType::Resolve)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_restore_open_incidents() {
+        let mut notifier = test_notifier();
+        notifier.pagerduty_webhook = Some(PagerDutyWebHook::new("key".to_string()));
+        notifier.opsgenie_webhook = Some(OpsgenieWebHook::new("key".to_string()));
+        assert_eq!(notifier.open_incidents(), OpenIncidents::default());
+
+        let trigger = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+        let pagerduty_webhook = notifier.pagerduty_webhook.as_ref().unwrap();
+        pagerduty_webhook.events("Error: delinquent", &trigger);
+        let opsgenie_webhook = notifier.opsgenie_webhook.as_ref().unwrap();
+        opsgenie_webhook.requests("Error: delinquent", &trigger);
+        let open_incidents = notifier.open_incidents();
+        assert_eq!(
+            open_incidents.pagerduty.iter().collect::<Vec<_>>(),
+            vec!["solana-watchtower-delinquent"]
+        );
+        assert_eq!(
+            open_incidents.opsgenie.iter().collect::<Vec<_>>(),
+            vec!["solana-watchtower-delinquent"]
+        );
+
+        // After a restart, the all clear resolves the incidents of the previous run
+        let mut restarted_notifier = test_notifier();
+        restarted_notifier.pagerduty_webhook = Some(PagerDutyWebHook::new("key".to_string()));
+        restarted_notifier.restore_open_incidents(&open_incidents);
+        let events = restarted_notifier
+            .pagerduty_webhook
+            .as_ref()
+            .unwrap()
+            .events("All clear", &NotificationType::Resolve);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["dedup_key"], "solana-watchtower-delinquent");
+        // Opsgenie is no longer configured, so its alert is dropped
+        assert!(restarted_notifier.open_incidents().opsgenie.is_empty());
+    }
+
+    #[test]
+    fn test_pagerduty_test_notification() {
+        let pagerduty_webhook = PagerDutyWebHook::new("key".to_string());
+        let events = pagerduty_webhook.events("test", &NotificationType::Test);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event_action"], "trigger");
+        assert_eq!(events[1]["event_action"], "resolve");
+        assert_eq!(events[0]["dedup_key"], events[1]["dedup_key"]);
+
+        // The test incident is not left for the all clear to resolve
+        assert!(pagerduty_webhook
+            .events("All clear", &NotificationType::Resolve)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pagerduty_webhook() {
+        let (url, request) = mock_endpoint("202 Accepted");
+        let mut pagerduty_webhook = PagerDutyWebHook::new("key".to_string());
+        pagerduty_webhook.events_url = url;
+        let mut notifier = test_notifier();
+        notifier.pagerduty_webhook = Some(pagerduty_webhook);
+
+        notifier.send(
+            "solana-watchtower: Error: balance: low",
+            &NotificationType::Trigger {
+                test: "balance",
+                severity: Severity::Warning,
+            },
+        );
+        let body: Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        assert_eq!(body["event_action"], "trigger");
+        assert_eq!(body["payload"]["severity"], "warning");
+    }
+
+    #[test]
+    fn test_send_to_incidents() {
+        let (url, request) = mock_endpoint("202 Accepted");
+        let mut pagerduty_webhook = PagerDutyWebHook::new("key".to_string());
+        pagerduty_webhook.events_url = url;
+        pagerduty_webhook
+            .triggered
+            .borrow_mut()
+            .insert(PagerDutyWebHook::dedup_key("balance"));
+        let mut notifier = test_notifier();
+        notifier.pagerduty_webhook = Some(pagerduty_webhook);
+        // Not listening, so it would fail if it were sent to
+        notifier.slack_webhook = Some("http://127.0.0.1:1".to_string());
+
+        assert_eq!(
+            notifier.send_to(
+                Channels::Incidents,
+                "solana-watchtower: All clear",
+                &NotificationType::Resolve
+            ),
+            vec![("PagerDuty", true)]
+        );
+        let body: Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        assert_eq!(body["event_action"], "resolve");
+        assert!(Channels::Messages.includes("Slack"));
+        assert!(!Channels::Messages.includes("PagerDuty"));
+    }
+
+    #[cfg(feature = "statuspage")]
+    #[test]
+    fn test_statuspage_incident_impact() {
+        assert_eq!(
+            StatuspageWebHook::incident_impact(Severity::Warning),
+            "minor"
+        );
+        assert_eq!(
+            StatuspageWebHook::incident_impact(Severity::Critical),
+            "critical"
+        );
+    }
+
+    #[cfg(feature = "statuspage")]
+    #[test]
+    fn test_statuspage_webhook() {
+        let (url, requests) = mock_server(vec![
+            ("201 Created", r#"{"id": "incident-1"}"#),
+            ("200 OK", "{}"),
+            ("200 OK", "{}"),
+        ]);
+        let mut statuspage_webhook =
+            StatuspageWebHook::new("key".to_string(), "page".to_string(), "eu-west:");
+        statuspage_webhook.api_url = url;
+        let trigger = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+        let client = Client::new();
+
+        // A failure opens an incident, and updates it while it keeps failing
+        assert_eq!(
+            statuspage_webhook.send(&client, "Error: delinquent", &trigger),
+            Some(true)
+        );
+        assert_eq!(
+            statuspage_webhook.send(&client, "Error: delinquent, still", &trigger),
+            Some(true)
+        );
+        assert_eq!(
+            statuspage_webhook.send(&client, "info", &NotificationType::Info),
+            None
+        );
+        assert_eq!(
+            statuspage_webhook.send(&client, "All clear", &NotificationType::Resolve),
+            Some(true)
+        );
+        // Nothing is left to resolve
+        assert_eq!(
+            statuspage_webhook.send(&client, "All clear", &NotificationType::Resolve),
+            None
+        );
+
+        let requests = requests.join().unwrap();
+        assert_eq!(requests[0].0, "POST /pages/page/incidents HTTP/1.1");
+        let create: Value = serde_json::from_str(&requests[0].1).unwrap();
+        assert_eq!(create["incident"]["name"], "eu-west: delinquent");
+        assert_eq!(create["incident"]["impact_override"], "critical");
+        assert_eq!(
+            requests[1].0,
+            "PATCH /pages/page/incidents/incident-1 HTTP/1.1"
+        );
+        let update: Value = serde_json::from_str(&requests[1].1).unwrap();
+        assert_eq!(update["incident"]["body"], "Error: delinquent, still");
+        assert_eq!(
+            requests[2].0,
+            "PATCH /pages/page/incidents/incident-1 HTTP/1.1"
+        );
+        let resolve: Value = serde_json::from_str(&requests[2].1).unwrap();
+        assert_eq!(resolve["incident"]["status"], "resolved");
+    }
+
+    #[cfg(feature = "systemd")]
+    #[test]
+    fn test_journal_entry() {
+        let entry = JournalNotifier::entry(
+            "solana-watchtower: Error: balance: low",
+            &NotificationType::Trigger {
+                test: "balance",
+                severity: Severity::Warning,
+            },
+        );
+        assert_eq!(
+            String::from_utf8(entry).unwrap(),
+            "MESSAGE=solana-watchtower: Error: balance: low\n\
+             PRIORITY=4\n\
+             SYSLOG_IDENTIFIER=solana-watchtower\n\
+             WT_SEVERITY=warning\n\
+             WT_TEST=balance\n"
+        );
+
+        // A multi-line message is length prefixed, and an info notification has no test
+        let entry = JournalNotifier::entry("a\nb", &NotificationType::Info);
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        expected.extend_from_slice(
+            b"PRIORITY=6\nSYSLOG_IDENTIFIER=solana-watchtower\nWT_SEVERITY=info\n",
+        );
+        assert_eq!(entry, expected);
+    }
+
+    #[test]
+    fn test_opsgenie_close_after_create() {
+        let opsgenie_webhook = OpsgenieWebHook::new("key".to_string());
+        let trigger = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+
+        // Nothing to close before a failure
+        assert!(opsgenie_webhook
+            .requests("All clear", &NotificationType::Resolve)
+            .is_empty());
+
+        let requests = opsgenie_webhook.requests(&"x".repeat(200), &trigger);
+        assert_eq!(requests.len(), 1);
+        let (url, body) = &requests[0];
+        assert_eq!(url, "https://api.opsgenie.com/v2/alerts");
+        assert_eq!(body["alias"], "solana-watchtower-delinquent");
+        assert_eq!(body["priority"], "P1");
+        assert_eq!(
+            body["message"].as_str().unwrap().chars().count(),
+            OPSGENIE_MAX_MESSAGE_LEN
+        );
+        assert_eq!(body["description"].as_str().unwrap().len(), 200);
+
+        // A repeated failure is created with the same alias, and only closed once
+        opsgenie_webhook.requests("Error: delinquent", &trigger);
+        let requests = opsgenie_webhook.requests("All clear", &NotificationType::Resolve);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].0,
+            "https://api.opsgenie.com/v2/alerts/solana-watchtower-delinquent/close?identifierType=alias"
+        );
+        assert!(opsgenie_webhook
+            .requests("All clear", &NotificationType::Resolve)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_opsgenie_webhook() {
+        let (url, request) = mock_endpoint("202 Accepted");
+        let mut opsgenie_webhook = OpsgenieWebHook::new("key".to_string());
+        opsgenie_webhook.api_url = url;
+        let mut notifier = test_notifier();
+        notifier.opsgenie_webhook = Some(opsgenie_webhook);
+
+        notifier.send(
+            "solana-watchtower: Error: balance: low",
+            &NotificationType::Trigger {
+                test: "balance",
+                severity: Severity::Warning,
+            },
+        );
+        let body: Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        assert_eq!(body["alias"], "solana-watchtower-balance");
+        assert_eq!(body["priority"], "P3");
+    }
+
+    #[test]
+    fn test_slack_webhook() {
+        let (url, request) = mock_endpoint("200 OK");
+        let mut notifier = test_notifier();
+        notifier.slack_webhook = Some(url);
+        assert_eq!(notifier.channels(), vec!["Slack"]);
+
+        notifier.send("solana-watchtower: test message", &NotificationType::Info);
+        let body: serde_json::Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        assert_eq!(body["text"], "solana-watchtower: test message");
+    }
+
+    #[test]
+    fn test_slack_payload() {
+        let critical = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+        assert_eq!(
+            slack_payload("solana-watchtower: Error: delinquent", &critical)["text"],
+            ":rotating_light: solana-watchtower: Error: delinquent"
+        );
+        assert_eq!(
+            slack_payload("solana-watchtower: All clear", &NotificationType::Resolve)["text"],
+            ":white_check_mark: solana-watchtower: All clear"
+        );
+        assert_eq!(
+            slack_payload("solana-watchtower: Notice", &NotificationType::Info)["text"],
+            "solana-watchtower: Notice"
+        );
+    }
+
+    #[test]
+    fn test_teams_webhook() {
+        let (url, request) = mock_endpoint("200 OK");
+        let mut notifier = test_notifier();
+        notifier.teams_webhook = Some(url);
+        assert_eq!(notifier.channels(), vec!["Teams"]);
+
+        let critical = NotificationType::Trigger {
+            test: "delinquent",
+            severity: Severity::Critical,
+        };
+        notifier.send("solana-watchtower: Error: delinquent", &critical);
+        let body: Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+        assert_eq!(body["@type"], "MessageCard");
+        assert_eq!(body["title"], "solana-watchtower: delinquent");
+        assert_eq!(body["themeColor"], "FF0000");
+        assert_eq!(body["text"], "solana-watchtower: Error: delinquent");
+
+        assert_eq!(
+            teams_payload(
+                "eu-west watchtower:",
+                "eu-west watchtower: All clear",
+                &NotificationType::Resolve
+            )["title"],
+            "eu-west watchtower: All clear"
+        );
+    }
+
+    #[cfg(feature = "sns")]
+    #[test]
+    fn test_sns_topic_region() {
+        assert_eq!(
+            sns_topic_region("arn:aws:sns:us-east-2:123456789012:watchtower"),
+            Some(Region::UsEast2)
+        );
+        assert_eq!(
+            sns_topic_region("arn:aws:sns:nowhere-1:123456789012:watchtower"),
+            None
+        );
+        assert_eq!(sns_topic_region("watchtower"), None);
+    }
+
+    #[test]
+    fn test_slack_webhook_failure() {
+        // A rejected message is only logged
+        let (url, request) = mock_endpoint("500 Internal Server Error");
+        let mut notifier = test_notifier();
+        notifier.slack_webhook = Some(url);
+
+        assert_eq!(
+            notifier.send("solana-watchtower: test message", &NotificationType::Info),
+            vec![("Slack", false)]
+        );
+        assert!(request.join().unwrap().contains("test message"));
+        assert!(notifier.notifications_sent().is_empty());
+    }
+
+    #[test]
+    fn test_send_skips_incident_backends() {
+        let (url, request) = mock_endpoint("200 OK");
+        let mut notifier = test_notifier();
+        notifier.slack_webhook = Some(url);
+        // An info notification has nothing to send to PagerDuty
+        notifier.pagerduty_webhook = Some(PagerDutyWebHook::new("key".to_string()));
+
+        assert_eq!(
+            notifier.send("solana-watchtower: Notice", &NotificationType::Info),
+            vec![("Slack", true)]
+        );
+        request.join().unwrap();
+        assert_eq!(
+            notifier
+                .notifications_sent()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![("Slack", 1)]
+        );
     }
 }
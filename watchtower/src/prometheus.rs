@@ -0,0 +1,221 @@
+//! A minimal HTTP server exposing the watchtower gauges in the Prometheus text format, for
+//! operators who scrape Prometheus rather than use the `solana_metrics` data points
+use crate::dashboard::{
+    CURRENT_STAKE_PERCENT, CYCLE_DURATION_SECONDS, DELINQUENT_VALIDATORS, NOTIFICATIONS_SENT,
+    NOTIFICATIONS_SUPPRESSED, SANITY_OK,
+};
+use log::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread::{self, Builder},
+    time::Duration,
+};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// The metrics of the last completed iteration of the main loop
+pub struct Metrics {
+    pub current_stake_percent: Option<u64>,
+    pub delinquent_validators: Option<usize>,
+    pub sanity_ok: bool,
+    pub cycle_duration: Duration,
+    /// Number of notifications sent since startup, by channel
+    pub notifications_sent: HashMap<&'static str, u64>,
+    /// Number of notifications suppressed by a cooldown since startup, by check
+    pub notifications_suppressed: HashMap<String, u64>,
+}
+
+/// Shared by the watchtowers of every `--cluster`, so that one endpoint serves them all
+#[derive(Clone)]
+pub struct PrometheusServer {
+    /// The metrics of each cluster, by its label
+    metrics: Arc<Mutex<BTreeMap<Option<String>, Metrics>>>,
+}
+
+impl PrometheusServer {
+    pub fn start(addr: &SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let metrics = Arc::new(Mutex::new(BTreeMap::new()));
+        let thread_metrics = metrics.clone();
+        Builder::new()
+            .name("watchtower-prometheus".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let metrics = thread_metrics.clone();
+                            thread::spawn(move || {
+                                if let Err(err) = handle_request(stream, &metrics) {
+                                    debug!("Prometheus request failed: {}", err);
+                                }
+                            });
+                        }
+                        Err(err) => warn!("Failed to accept Prometheus connection: {}", err),
+                    }
+                }
+            })?;
+        info!("Prometheus metrics listening on {}{}", addr, METRICS_PATH);
+        Ok(Self { metrics })
+    }
+
+    /// Replace the exposed metrics of `cluster` with those of the iteration that just completed
+    pub fn update(&self, cluster: Option<&str>, metrics: Metrics) {
+        self.metrics
+            .lock()
+            .unwrap()
+            .insert(cluster.map(|cluster| cluster.to_string()), metrics);
+    }
+}
+
+/// The `{name="value",...}` label set of a sample, empty without any labels
+fn labels(cluster: Option<&str>, label: Option<(&str, &str)>) -> String {
+    let labels: Vec<_> = cluster
+        .map(|cluster| ("cluster", cluster))
+        .into_iter()
+        .chain(label)
+        .map(|(name, value)| format!("{}=\"{}\"", name, value))
+        .collect();
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+/// A gauge with a sample per cluster, left out when no cluster has a value
+fn gauge<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    values: impl Iterator<Item = (Option<&'a str>, String)>,
+) {
+    let values: Vec<_> = values.collect();
+    if values.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (cluster, value) in values {
+        let _ = writeln!(out, "{}{} {}", name, labels(cluster, None), value);
+    }
+}
+
+/// A counter with a sample per cluster and `label` value
+fn counter<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: impl Iterator<Item = (Option<&'a str>, &'a str, u64)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let mut values: Vec<_> = values.collect();
+    values.sort();
+    for (cluster, key, count) in values {
+        let _ = writeln!(
+            out,
+            "{}{} {}",
+            name,
+            labels(cluster, Some((label, key))),
+            count
+        );
+    }
+}
+
+fn render(metrics: &BTreeMap<Option<String>, Metrics>) -> String {
+    let clusters = || {
+        metrics
+            .iter()
+            .map(|(cluster, metrics)| (cluster.as_deref(), metrics))
+    };
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        CURRENT_STAKE_PERCENT,
+        "Percentage of the total stake that is current",
+        clusters().filter_map(|(cluster, metrics)| {
+            metrics
+                .current_stake_percent
+                .map(|current_stake_percent| (cluster, current_stake_percent.to_string()))
+        }),
+    );
+    gauge(
+        &mut out,
+        DELINQUENT_VALIDATORS,
+        "Number of delinquent validators",
+        clusters().filter_map(|(cluster, metrics)| {
+            metrics
+                .delinquent_validators
+                .map(|delinquent_validators| (cluster, delinquent_validators.to_string()))
+        }),
+    );
+    gauge(
+        &mut out,
+        SANITY_OK,
+        "1 when the last iteration had no sanity failures",
+        clusters().map(|(cluster, metrics)| (cluster, (metrics.sanity_ok as u8).to_string())),
+    );
+    gauge(
+        &mut out,
+        CYCLE_DURATION_SECONDS,
+        "Duration of the last iteration, excluding the sleep",
+        clusters()
+            .map(|(cluster, metrics)| (cluster, metrics.cycle_duration.as_secs_f64().to_string())),
+    );
+
+    counter(
+        &mut out,
+        NOTIFICATIONS_SENT,
+        "Notifications sent since startup",
+        "channel",
+        clusters().flat_map(|(cluster, metrics)| {
+            metrics
+                .notifications_sent
+                .iter()
+                .map(move |(channel, count)| (cluster, *channel, *count))
+        }),
+    );
+    counter(
+        &mut out,
+        NOTIFICATIONS_SUPPRESSED,
+        "Notifications suppressed by a cooldown since startup",
+        "test",
+        clusters().flat_map(|(cluster, metrics)| {
+            metrics
+                .notifications_suppressed
+                .iter()
+                .map(move |(test, count)| (cluster, test.as_str(), *count))
+        }),
+    );
+    out
+}
+
+fn handle_request(
+    stream: TcpStream,
+    metrics: &Mutex<BTreeMap<Option<String>, Metrics>>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, body) = if path == METRICS_PATH {
+        // A cluster is not exposed until its first iteration completes
+        let body = render(&metrics.lock().unwrap());
+        ("200 OK", body)
+    } else {
+        ("404 Not Found", "not found".to_string())
+    };
+    write!(
+        &stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,21 @@
+//! SIGHUP handling, used to request a reload of the config file
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn handle_sighup(_signal: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub fn install_sighup_handler() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGHUP, handle_sighup as libc::sighandler_t);
+    }
+}
+
+/// Returns true if a SIGHUP was received since the last call
+pub fn take_reload_request() -> bool {
+    RELOAD_REQUESTED.swap(false, Ordering::Relaxed)
+}
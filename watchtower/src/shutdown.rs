@@ -0,0 +1,41 @@
+//! SIGTERM and SIGINT handling, used to stop the main loop cleanly
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// How often an interruptible sleep checks for a shutdown request
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[cfg(unix)]
+extern "C" fn handle_shutdown_signal(_signal: libc::c_int) {
+    SHUTDOWN_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub fn install_shutdown_handler() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGTERM, handle_shutdown_signal as libc::sighandler_t);
+        libc::signal(libc::SIGINT, handle_shutdown_signal as libc::sighandler_t);
+    }
+}
+
+/// Returns true once a SIGTERM or SIGINT was received
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Sleep for `duration`, returning early if a shutdown is requested
+pub fn sleep(duration: Duration) {
+    let start = Instant::now();
+    while !shutdown_requested() {
+        let elapsed = start.elapsed();
+        if elapsed >= duration {
+            break;
+        }
+        thread::sleep(SHUTDOWN_POLL_INTERVAL.min(duration - elapsed));
+    }
+}
@@ -0,0 +1,346 @@
+//! Checks that the cluster is making progress: its slot, epoch, transaction count and blockhash
+//! advance from one interval to the next.  Also notifies the `--notify-at-slot` and
+//! `--notify-at-epoch` milestones and changes to the epoch schedule
+use crate::{
+    data_unavailable,
+    state::{State, StateFile},
+    CheckContext, CheckResults,
+};
+use log::*;
+use solana_sdk::{
+    clock::{Epoch, Slot},
+    hash::Hash,
+};
+
+/// What the progress checks remember of the previous interval
+#[derive(Default)]
+pub struct SlotChecks {
+    last_slot: Slot,
+    last_recent_slot: Slot,
+    recent_slot_stalled: bool,
+    /// Epoch and slot index of the last `--monitor-epoch-progress` check
+    last_epoch_position: Option<(Epoch, u64)>,
+    last_transaction_count: u64,
+    /// Number of consecutive intervals the transaction count has not advanced for
+    tx_count_stalled_intervals: usize,
+    last_recent_blockhash: Hash,
+}
+
+impl SlotChecks {
+    pub fn check(&mut self, context: &CheckContext, results: &mut CheckResults) {
+        self.check_slot(context, results);
+        self.check_epoch(context, results);
+        self.check_transaction_count(context, results);
+        self.check_recent_blockhash(context, results);
+    }
+
+    fn check_slot(&mut self, context: &CheckContext, results: &mut CheckResults) {
+        let CheckContext {
+            config,
+            cluster_info,
+            influx_output,
+            log_cycle_details,
+            ..
+        } = *context;
+        let slot = match &cluster_info.slot {
+            Ok(slot) => *slot,
+            Err(err) => {
+                results.failures.push(data_unavailable(
+                    "Slot",
+                    &["finalization-lag", "slow-block-production", "slot-stall"],
+                    err,
+                ));
+                return;
+            }
+        };
+        if log_cycle_details {
+            info!("Current slot: {}", slot);
+        }
+        let recent_slot = match &cluster_info.recent_slot {
+            Some(Ok(recent_slot)) => Some(*recent_slot),
+            Some(Err(err)) => {
+                warn!("Failed to get most recent slot: {:?}", err);
+                None
+            }
+            None => None,
+        };
+
+        if let (Some(max_finalization_lag_slots), Some(recent_slot)) =
+            (config.max_finalization_lag_slots, recent_slot)
+        {
+            let finalization_lag = recent_slot.saturating_sub(slot);
+            submit_datapoint!(
+                influx_output,
+                Level::Info,
+                "watchtower-finalization-lag",
+                ("slots", finalization_lag, i64)
+            );
+            if finalization_lag > max_finalization_lag_slots {
+                results.failures.push((
+                    "finalization-lag",
+                    format!(
+                        "Finalized slot {} is {} slots behind slot {}",
+                        slot, finalization_lag, recent_slot
+                    ),
+                ));
+            }
+        }
+
+        // The slot is a more direct liveness signal than the transaction count
+        submit_datapoint!(
+            influx_output,
+            Level::Info,
+            "watchtower-finalized-slot",
+            ("slot", slot, i64)
+        );
+        if slot > self.last_slot {
+            if let Some(min_slots_per_interval) = config.min_slots_per_interval {
+                // There is nothing to compare against on the first interval
+                let slots_advanced = slot - self.last_slot;
+                if self.last_slot > 0 && slots_advanced < min_slots_per_interval {
+                    results.failures.push((
+                        "slow-block-production",
+                        format!(
+                            "Finalized slot only advanced by {} slots, to {}",
+                            slots_advanced, slot
+                        ),
+                    ));
+                }
+            }
+            self.last_slot = slot;
+        } else {
+            results.failures.push((
+                "slot-stall",
+                format!(
+                    "Finalized slot is not advancing: {} <= {}",
+                    slot, self.last_slot
+                ),
+            ));
+        }
+
+        if config.monitor_slot_advancement {
+            // The most recent slot is noisier, so a stall is only an early warning
+            if let Some(recent_slot) = recent_slot {
+                submit_datapoint!(
+                    influx_output,
+                    Level::Info,
+                    "watchtower-processed-slot",
+                    ("slot", recent_slot, i64)
+                );
+                if recent_slot > self.last_recent_slot {
+                    self.last_recent_slot = recent_slot;
+                    self.recent_slot_stalled = false;
+                } else if !self.recent_slot_stalled
+                    && !config.disabled_checks.contains("slot-stall")
+                {
+                    self.recent_slot_stalled = true;
+                    let msg = format!(
+                        "Most recent slot is not advancing: {} <= {}",
+                        recent_slot, self.last_recent_slot
+                    );
+                    warn!("{}", msg);
+                    context.notify_info(&msg);
+                }
+            }
+        }
+    }
+
+    fn check_epoch(&mut self, context: &CheckContext, results: &mut CheckResults) {
+        let CheckContext {
+            cluster_info,
+            influx_output,
+            log_cycle_details,
+            ..
+        } = *context;
+        let epoch_info = match &cluster_info.epoch_info {
+            Some(Ok(epoch_info)) => epoch_info,
+            Some(Err(err)) => {
+                results
+                    .failures
+                    .push(data_unavailable("Epoch info", &["epoch-stall"], err));
+                return;
+            }
+            None => return,
+        };
+        let slots_remaining = epoch_info
+            .slots_in_epoch
+            .saturating_sub(epoch_info.slot_index);
+        if log_cycle_details {
+            info!(
+                "Current epoch: {}, slot {} of {}, {} slots remaining",
+                epoch_info.epoch, epoch_info.slot_index, epoch_info.slots_in_epoch, slots_remaining
+            );
+        }
+        submit_datapoint!(
+            influx_output,
+            Level::Info,
+            "watchtower-epoch",
+            ("epoch", epoch_info.epoch, i64),
+            ("slot_index", epoch_info.slot_index, i64),
+            ("slots_remaining", slots_remaining, i64)
+        );
+        // The position only moves forward, through the epoch and then to the next
+        let epoch_position = (epoch_info.epoch, epoch_info.slot_index);
+        match self.last_epoch_position {
+            Some(last_position) if epoch_position <= last_position => {
+                results.failures.push((
+                    "epoch-stall",
+                    format!(
+                        "Epoch {} is not advancing, stuck at slot {} of {}",
+                        epoch_info.epoch, epoch_info.slot_index, epoch_info.slots_in_epoch
+                    ),
+                ));
+            }
+            _ => self.last_epoch_position = Some(epoch_position),
+        }
+    }
+
+    fn check_transaction_count(&mut self, context: &CheckContext, results: &mut CheckResults) {
+        let CheckContext {
+            config,
+            cluster_info,
+            log_cycle_details,
+            ..
+        } = *context;
+        let transaction_count = match &cluster_info.transaction_count {
+            Ok(transaction_count) => *transaction_count,
+            Err(err) => {
+                results.failures.push(data_unavailable(
+                    "Transaction count",
+                    &["transaction-count", "tx-count-regression"],
+                    err,
+                ));
+                return;
+            }
+        };
+        if log_cycle_details {
+            info!("Current transaction count: {}", transaction_count);
+        }
+        if transaction_count > self.last_transaction_count {
+            self.last_transaction_count = transaction_count;
+            self.tx_count_stalled_intervals = 0;
+        } else if self.last_transaction_count - transaction_count
+            > config.tx_count_regression_margin
+        {
+            results.failures.push((
+                "tx-count-regression",
+                format!(
+                    "Transaction count went backwards, the RPC node may be on a different fork \
+                     or was reset: {} < {}",
+                    transaction_count, self.last_transaction_count
+                ),
+            ));
+            // Check for advancement from the new count
+            self.last_transaction_count = transaction_count;
+            self.tx_count_stalled_intervals = 0;
+        } else {
+            // The count of a low traffic cluster can stay flat for a while
+            self.tx_count_stalled_intervals += 1;
+            if self.tx_count_stalled_intervals >= config.tx_count_stall_intervals {
+                results.failures.push((
+                    "transaction-count",
+                    format!(
+                        "Transaction count is not advancing: {} <= {}",
+                        transaction_count, self.last_transaction_count
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn check_recent_blockhash(&mut self, context: &CheckContext, results: &mut CheckResults) {
+        let recent_blockhash = match &context.cluster_info.recent_blockhash {
+            Ok(recent_blockhash) => *recent_blockhash,
+            Err(err) => {
+                results.failures.push(data_unavailable(
+                    "Recent blockhash",
+                    &["recent-blockhash"],
+                    err,
+                ));
+                return;
+            }
+        };
+        if context.log_cycle_details {
+            info!("Recent blockhash: {}", recent_blockhash);
+        }
+        if recent_blockhash != self.last_recent_blockhash {
+            self.last_recent_blockhash = recent_blockhash;
+        } else {
+            results.failures.push((
+                "recent-blockhash",
+                format!("Unable to get new blockhash: {}", recent_blockhash),
+            ));
+        }
+    }
+}
+
+/// Notify the `--notify-at-slot` and `--notify-at-epoch` milestones the cluster has reached,
+/// recording them in the state so that each is only notified once
+pub fn check_milestones(context: &CheckContext, state: &mut State, state_file: &StateFile) {
+    let config = context.config;
+    let pending_slots: Vec<_> = config
+        .notify_at_slots
+        .difference(&state.reached_slots)
+        .cloned()
+        .collect();
+    let pending_epochs: Vec<_> = config
+        .notify_at_epochs
+        .difference(&state.reached_epochs)
+        .cloned()
+        .collect();
+    if pending_slots.is_empty() && pending_epochs.is_empty() {
+        return;
+    }
+    match context.rpc_client.get_epoch_info() {
+        Ok(epoch_info) => {
+            let mut milestones = vec![];
+            for slot in pending_slots {
+                if epoch_info.absolute_slot >= slot {
+                    milestones.push(format!("slot {}", slot));
+                    state.reached_slots.insert(slot);
+                }
+            }
+            for epoch in pending_epochs {
+                if epoch_info.epoch >= epoch {
+                    milestones.push(format!("epoch {}", epoch));
+                    state.reached_epochs.insert(epoch);
+                }
+            }
+            if !milestones.is_empty() {
+                let msg = format!("Cluster reached {}", milestones.join(", "));
+                info!("{}", msg);
+                context.notify_info(&msg);
+                state_file.save(state);
+            }
+        }
+        Err(err) => warn!("Failed to get epoch info: {:?}", err),
+    }
+}
+
+/// Notify a change of the number of slots per epoch, for `--monitor-epoch-schedule`
+pub fn check_epoch_schedule(context: &CheckContext, state: &mut State, state_file: &StateFile) {
+    if !context.config.monitor_epoch_schedule {
+        return;
+    }
+    match context.rpc_client.get_epoch_schedule() {
+        Ok(epoch_schedule) => {
+            if let Some(last_epoch_schedule) = state.epoch_schedule {
+                if last_epoch_schedule.slots_per_epoch != epoch_schedule.slots_per_epoch
+                    && !context.config.disabled_checks.contains("epoch-schedule")
+                {
+                    let msg = format!(
+                        "Slots per epoch changed: {} -> {}",
+                        last_epoch_schedule.slots_per_epoch, epoch_schedule.slots_per_epoch
+                    );
+                    warn!("{}", msg);
+                    context.notify_info(&msg);
+                }
+            }
+            if state.epoch_schedule != Some(epoch_schedule) {
+                state.epoch_schedule = Some(epoch_schedule);
+                state_file.save(state);
+            }
+        }
+        Err(err) => warn!("Failed to get epoch schedule: {:?}", err),
+    }
+}
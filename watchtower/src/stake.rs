@@ -0,0 +1,1067 @@
+//! Checks of the vote accounts: the validator count, the stake of the cluster and where it is
+//! delegated, the delinquency and voting of the monitored validators and their commission.
+//! Also notifies the deactivation of the `--monitor-stake-account` stake accounts
+use crate::{
+    data_unavailable, inventory::Inventory, percent_change, CheckContext, CheckResults, RpcRetry,
+};
+use log::*;
+use rayon::ThreadPool;
+use solana_client::{
+    client_error::Result as ClientResult, rpc_client::RpcClient, rpc_response::RpcVoteAccountStatus,
+};
+use solana_sdk::{
+    clock::{Epoch, Slot},
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+    sysvar::{
+        stake_history::{self, StakeHistory},
+        Sysvar,
+    },
+};
+use solana_stake_program::stake_state::{Delegation, StakeState};
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
+
+/// Counts the consecutive intervals each validator identity has been seen delinquent for
+#[derive(Default)]
+struct DelinquentCounts(HashMap<String, usize>);
+
+impl DelinquentCounts {
+    /// Count another interval for each of the `delinquent` validator identities, and reset the
+    /// count of every other validator
+    fn update<'a>(&mut self, delinquent: impl IntoIterator<Item = &'a str>) {
+        self.0 = delinquent
+            .into_iter()
+            .map(|validator_identity| {
+                let count = self.0.get(validator_identity).cloned().unwrap_or_default();
+                (validator_identity.to_string(), count + 1)
+            })
+            .collect();
+    }
+
+    /// Whether `validator_identity` has been delinquent for at least `grace_intervals`
+    /// consecutive intervals
+    fn is_reported(&self, validator_identity: &str, grace_intervals: usize) -> bool {
+        self.0
+            .get(validator_identity)
+            .map(|count| *count >= grace_intervals)
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StakeActivation {
+    Active,
+    Deactivating,
+    Deactivated,
+}
+
+/// What the vote account and stake account checks remember of the previous intervals
+#[derive(Default)]
+pub struct StakeChecks {
+    delinquent_counts: DelinquentCounts,
+    last_validator_count: Option<usize>,
+    /// Total active stake of the cluster, in lamports
+    last_total_stake: Option<u64>,
+    /// Commission of the vote accounts of the monitored validators
+    last_commissions: HashMap<String, u8>,
+    vote_account_identities: HashMap<String, String>,
+    /// Vote credits of each monitored validator, and the number of consecutive intervals they
+    /// have not increased for
+    last_vote_credits: HashMap<String, (u64, usize)>,
+    /// Newly activated stake by vote account as of an epoch, for --new-stake-grace-epochs
+    new_stakes: Option<(Epoch, HashMap<Pubkey, u64>)>,
+    last_stake_activations: HashMap<Pubkey, StakeActivation>,
+}
+
+/// Percentage of the `total_stake` that `stake` is
+fn stake_impact_percent(stake: u64, total_stake: u64) -> f64 {
+    if total_stake > 0 {
+        stake as f64 * 100. / total_stake as f64
+    } else {
+        0.
+    }
+}
+
+impl StakeChecks {
+    /// Resume counting the delinquent intervals of each validator from `delinquent_counts`
+    pub fn new(delinquent_counts: HashMap<String, usize>) -> Self {
+        Self {
+            delinquent_counts: DelinquentCounts(delinquent_counts),
+            ..Self::default()
+        }
+    }
+
+    /// The number of consecutive intervals each validator identity has been delinquent for, to
+    /// be saved in the state
+    pub fn delinquent_counts(&self) -> &HashMap<String, usize> {
+        &self.delinquent_counts.0
+    }
+
+    /// Check the vote accounts of the cluster.  `validator_identity_pubkeys` are the monitored
+    /// validators, and `missing_vote_accounts` the monitored vote accounts that are not found
+    pub fn check(
+        &mut self,
+        context: &CheckContext,
+        validator_identity_pubkeys: &[String],
+        missing_vote_accounts: &[String],
+        inventory: &mut Inventory,
+        results: &mut CheckResults,
+    ) {
+        let CheckContext {
+            config,
+            cluster_info,
+            influx_output,
+            log_cycle_details,
+            ..
+        } = *context;
+        let vote_accounts = match &cluster_info.vote_accounts {
+            Ok(vote_accounts) => vote_accounts,
+            Err(err) => {
+                results.failures.push(data_unavailable(
+                    "Vote accounts",
+                    &[
+                        "current-stake",
+                        "delinquent",
+                        "delinquent-stake",
+                        "stake-drop",
+                        "superminority-low",
+                        "validator-count-drop",
+                        "vote-credits",
+                        "root-lag",
+                        "commission-changed",
+                        "commission-too-high",
+                        "leader-slots",
+                    ],
+                    err,
+                ));
+                return;
+            }
+        };
+        if log_cycle_details {
+            info!("Current validator count: {}", vote_accounts.current.len());
+            info!(
+                "Delinquent validator count: {}",
+                vote_accounts.delinquent.len()
+            );
+        }
+
+        let validator_count = vote_accounts.current.len();
+        submit_datapoint!(
+            influx_output,
+            Level::Info,
+            "watchtower-validator-count",
+            ("current", validator_count, i64),
+            ("delinquent", vote_accounts.delinquent.len(), i64)
+        );
+        if let (Some(max_drop_percent), Some(last_validator_count)) = (
+            config.max_validator_count_drop_percent,
+            self.last_validator_count,
+        ) {
+            let drop_percent = if validator_count < last_validator_count {
+                percent_change(last_validator_count as u64, validator_count as u64)
+            } else {
+                0.
+            };
+            if drop_percent > max_drop_percent {
+                results.failures.push((
+                    "validator-count-drop",
+                    format!(
+                        "Current validator count dropped {:.1}% from {} to {}",
+                        drop_percent, last_validator_count, validator_count
+                    ),
+                ));
+            }
+        }
+        self.last_validator_count = Some(validator_count);
+
+        let (total_stake, total_delinquent_stake) =
+            self.check_stake(context, vote_accounts, results);
+
+        self.delinquent_counts.update(
+            vote_accounts
+                .delinquent
+                .iter()
+                .map(|vai| vai.node_pubkey.as_str()),
+        );
+        let monitor_cluster =
+            config.validator_identity_pubkeys.is_empty() && config.vote_account_pubkeys.is_empty();
+        if monitor_cluster {
+            let delinquent_count = vote_accounts
+                .delinquent
+                .iter()
+                .filter(|vai| {
+                    self.delinquent_counts
+                        .is_reported(&vai.node_pubkey, config.delinquent_grace_intervals)
+                })
+                .count();
+            if delinquent_count > config.max_delinquent_validators {
+                results.failures.push((
+                    "delinquent",
+                    format!("{} delinquent validators", delinquent_count),
+                ));
+                results.stake_impacts.insert(
+                    "delinquent",
+                    stake_impact_percent(total_delinquent_stake, total_stake),
+                );
+            }
+        } else {
+            self.check_validators(
+                context,
+                vote_accounts,
+                validator_identity_pubkeys,
+                missing_vote_accounts,
+                total_stake,
+                results,
+            );
+        }
+
+        self.check_commissions(
+            context,
+            vote_accounts,
+            validator_identity_pubkeys,
+            inventory,
+            results,
+        );
+
+        if config.monitor_all_current {
+            update_inventory(context, vote_accounts, inventory);
+        }
+    }
+
+    /// Check the active, current and delinquent stake of the cluster, returning the total stake
+    /// the impact of a failure is measured against and the delinquent part of it
+    fn check_stake(
+        &mut self,
+        context: &CheckContext,
+        vote_accounts: &RpcVoteAccountStatus,
+        results: &mut CheckResults,
+    ) -> (u64, u64) {
+        let CheckContext {
+            config,
+            rpc_client,
+            thread_pool,
+            influx_output,
+            log_cycle_details,
+            ..
+        } = *context;
+        let total_current_stake = vote_accounts
+            .current
+            .iter()
+            .fold(0, |acc, vote_account| acc + vote_account.activated_stake);
+        let total_delinquent_stake = vote_accounts
+            .delinquent
+            .iter()
+            .fold(0, |acc, vote_account| acc + vote_account.activated_stake);
+
+        // The current stake percentage stays the same when stake leaves the cluster as a whole,
+        // such as in a mass unstaking
+        let total_active_stake = total_current_stake + total_delinquent_stake;
+        if let (Some(max_drop_percent), Some(last_total_stake)) =
+            (config.max_stake_drop_percent, self.last_total_stake)
+        {
+            let drop_percent = if total_active_stake < last_total_stake {
+                percent_change(last_total_stake, total_active_stake)
+            } else {
+                0.
+            };
+            if drop_percent > max_drop_percent {
+                results.failures.push((
+                    "stake-drop",
+                    format!(
+                        "Total active stake dropped {:.1}% from {} SOL to {} SOL",
+                        drop_percent,
+                        lamports_to_sol(last_total_stake),
+                        lamports_to_sol(total_active_stake)
+                    ),
+                ));
+            }
+        }
+        self.last_total_stake = Some(total_active_stake);
+
+        let superminority = get_superminority(
+            vote_accounts
+                .current
+                .iter()
+                .chain(vote_accounts.delinquent.iter())
+                .map(|vote_account| vote_account.activated_stake),
+        );
+        submit_datapoint!(
+            influx_output,
+            Level::Info,
+            "watchtower-superminority",
+            ("count", superminority, i64)
+        );
+        if let Some(min_superminority) = config.min_superminority {
+            if superminority < min_superminority {
+                results.failures.push((
+                    "superminority-low",
+                    format!(
+                        "Only {} validators hold more than a third of the active stake",
+                        superminority
+                    ),
+                ));
+            }
+        }
+
+        let total_delinquent_stake = match config.new_stake_grace_epochs {
+            Some(new_stake_grace_epochs) if total_delinquent_stake > 0 => {
+                let new_delinquent_stake = get_new_delinquent_stake(
+                    rpc_client,
+                    thread_pool,
+                    config.rpc_retry,
+                    vote_accounts,
+                    new_stake_grace_epochs,
+                    &mut self.new_stakes,
+                )
+                .unwrap_or_else(|err| {
+                    warn!("Failed to get newly activated stake: {:?}", err);
+                    0
+                });
+                if new_delinquent_stake > 0 {
+                    info!(
+                        "Ignoring {} SOL of newly activated delinquent stake",
+                        lamports_to_sol(new_delinquent_stake)
+                    );
+                }
+                total_delinquent_stake.saturating_sub(new_delinquent_stake)
+            }
+            _ => total_delinquent_stake,
+        };
+
+        let total_stake = total_current_stake + total_delinquent_stake;
+        // No stake at all is reported as none of it being current
+        let stake_percent = if total_stake > 0 {
+            total_current_stake * 100 / total_stake
+        } else {
+            0
+        };
+        results.current_stake_percent = Some(stake_percent);
+        if log_cycle_details {
+            info!(
+                "Current stake: {}% | Total stake: {} SOL, current stake: {} SOL, delinquent: {} SOL",
+                stake_percent,
+                lamports_to_sol(total_stake),
+                lamports_to_sol(total_current_stake),
+                lamports_to_sol(total_delinquent_stake)
+            );
+        }
+
+        let delinquent_stake_percent = stake_impact_percent(total_delinquent_stake, total_stake);
+        results.delinquent_stake_percent = Some(delinquent_stake_percent);
+
+        if let Some(max_delinquent_stake_percent) = config.max_delinquent_stake_percent {
+            if delinquent_stake_percent > max_delinquent_stake_percent {
+                results.failures.push((
+                    "delinquent-stake",
+                    format!("Delinquent stake is {:.2}%", delinquent_stake_percent),
+                ));
+                results
+                    .stake_impacts
+                    .insert("delinquent-stake", delinquent_stake_percent);
+            }
+        }
+
+        if config.monitor_active_stake && stake_percent < config.active_stake_threshold {
+            results.failures.push((
+                "current-stake",
+                format!("Current stake is {}%", stake_percent),
+            ));
+            // The impact is all of the stake that isn't current, not only the delinquent stake
+            results.stake_impacts.insert(
+                "current-stake",
+                stake_impact_percent(total_stake - total_current_stake, total_stake),
+            );
+        }
+
+        (total_stake, total_delinquent_stake)
+    }
+
+    /// Check that each of the monitored validators is current, voting and rooting, and notify
+    /// the rotation of their identities
+    fn check_validators(
+        &mut self,
+        context: &CheckContext,
+        vote_accounts: &RpcVoteAccountStatus,
+        validator_identity_pubkeys: &[String],
+        missing_vote_accounts: &[String],
+        total_stake: u64,
+        results: &mut CheckResults,
+    ) {
+        let CheckContext {
+            config,
+            cluster_info,
+            in_maintenance,
+            ..
+        } = *context;
+        let mut errors = vec![];
+        let mut affected_stake = Some(0);
+        // Monitored validators that are reported delinquent or missing
+        let mut down_validators = vec![];
+        for validator_identity in validator_identity_pubkeys
+            .iter()
+            .filter(|validator_identity| {
+                !in_maintenance.contains(*validator_identity)
+                    && config.alert_on_delinquency(validator_identity)
+            })
+        {
+            let validator_name = config.validator_name(validator_identity);
+            if let Some(vai) = vote_accounts
+                .delinquent
+                .iter()
+                .find(|vai| vai.node_pubkey == *validator_identity)
+            {
+                if self
+                    .delinquent_counts
+                    .is_reported(validator_identity, config.delinquent_grace_intervals)
+                {
+                    errors.push(format!("{} delinquent", validator_name));
+                    down_validators.push(validator_identity);
+                    affected_stake = affected_stake.map(|stake| stake + vai.activated_stake);
+                }
+            } else if !vote_accounts
+                .current
+                .iter()
+                .any(|vai| vai.node_pubkey == *validator_identity)
+            {
+                errors.push(format!("{} missing", validator_name));
+                down_validators.push(validator_identity);
+                // The stake of a missing validator is unknown
+                affected_stake = None;
+            }
+        }
+        // Neither the vote account nor its validator identity is known
+        for vote_account_pubkey in missing_vote_accounts {
+            errors.push(format!("Vote account {} missing", vote_account_pubkey));
+            affected_stake = None;
+        }
+
+        // A validator that is down when it is scheduled to lead will skip its blocks
+        match &cluster_info.upcoming_leader_slots {
+            Some(Ok(upcoming_leader_slots)) => {
+                let leader_slots_errors: Vec<_> = down_validators
+                    .iter()
+                    .filter_map(|validator_identity| {
+                        let upcoming_slots = upcoming_leader_slots.get(*validator_identity)?;
+                        Some(format!(
+                            "{} has {} upcoming leader slots, the next at slot {}",
+                            config.validator_name(validator_identity),
+                            upcoming_slots.len(),
+                            upcoming_slots[0]
+                        ))
+                    })
+                    .collect();
+                if !leader_slots_errors.is_empty() {
+                    results
+                        .affected_validators
+                        .insert("leader-slots", leader_slots_errors.len());
+                    results
+                        .failures
+                        .push(("leader-slots", leader_slots_errors.join(", ")));
+                }
+            }
+            Some(Err(err)) => {
+                results
+                    .failures
+                    .push(data_unavailable("Leader schedule", &["leader-slots"], err))
+            }
+            None => {}
+        }
+
+        self.check_vote_credits(context, vote_accounts, validator_identity_pubkeys, results);
+
+        // A validator can keep voting, and so not be delinquent, while its root falls behind
+        // the cluster
+        if let (Some(max_root_distance), Ok(slot)) = (config.max_root_distance, &cluster_info.slot)
+        {
+            let root_lag_errors: Vec<_> = get_root_lags(
+                vote_accounts,
+                validator_identity_pubkeys,
+                *slot,
+                max_root_distance,
+            )
+            .into_iter()
+            .filter(|(validator_identity, _, _)| !in_maintenance.contains(*validator_identity))
+            .map(|(validator_identity, root_slot, root_distance)| {
+                format!(
+                    "{} root slot {} is {} slots behind slot {}",
+                    config.validator_name(validator_identity),
+                    root_slot,
+                    root_distance,
+                    slot
+                )
+            })
+            .collect();
+            if !root_lag_errors.is_empty() {
+                results
+                    .affected_validators
+                    .insert("root-lag", root_lag_errors.len());
+                results
+                    .failures
+                    .push(("root-lag", root_lag_errors.join(", ")));
+            }
+        }
+
+        if !errors.is_empty() {
+            results.failures.push(("delinquent", errors.join(",")));
+            results
+                .affected_validators
+                .insert("delinquent", errors.len());
+            if let Some(affected_stake) = affected_stake {
+                results.stake_impacts.insert(
+                    "delinquent",
+                    stake_impact_percent(affected_stake, total_stake),
+                );
+            }
+        }
+
+        for rotation in get_identity_rotations(
+            vote_accounts,
+            validator_identity_pubkeys,
+            &mut self.vote_account_identities,
+        ) {
+            if !config.disabled_checks.contains("identity-rotated") {
+                warn!("{}", rotation);
+                context.notify_info(&rotation);
+            }
+        }
+    }
+
+    /// Check that each of the current monitored validators keeps earning vote credits
+    fn check_vote_credits(
+        &mut self,
+        context: &CheckContext,
+        vote_accounts: &RpcVoteAccountStatus,
+        validator_identity_pubkeys: &[String],
+        results: &mut CheckResults,
+    ) {
+        let CheckContext {
+            config,
+            in_maintenance,
+            ..
+        } = *context;
+        let mut credits_errors = vec![];
+        for validator_identity in validator_identity_pubkeys.iter() {
+            let current_vai = vote_accounts
+                .current
+                .iter()
+                .find(|vai| vai.node_pubkey == *validator_identity);
+            let vai = match current_vai {
+                Some(vai) => vai,
+                None => {
+                    // Delinquent and missing validators are reported as such, start counting again
+                    // once they are back
+                    self.last_vote_credits.remove(validator_identity);
+                    continue;
+                }
+            };
+            let credits = vai
+                .epoch_credits
+                .last()
+                .map(|(_, credits, _)| *credits)
+                .unwrap_or_default();
+            let stalled_intervals = match self.last_vote_credits.get(validator_identity) {
+                Some((last_credits, stalled_intervals)) if credits <= *last_credits => {
+                    stalled_intervals + 1
+                }
+                _ => 0,
+            };
+            self.last_vote_credits
+                .insert(validator_identity.clone(), (credits, stalled_intervals));
+            if stalled_intervals >= config.credits_stall_intervals
+                && !in_maintenance.contains(validator_identity)
+            {
+                credits_errors.push(format!(
+                    "{} has earned no vote credits in {} intervals",
+                    config.validator_name(validator_identity),
+                    stalled_intervals
+                ));
+            }
+        }
+        if !credits_errors.is_empty() {
+            results
+                .affected_validators
+                .insert("vote-credits", credits_errors.len());
+            results
+                .failures
+                .push(("vote-credits", credits_errors.join(", ")));
+        }
+    }
+
+    /// Check the commission of the vote accounts of the monitored validators, and of any
+    /// validator given its own `--max-commission`
+    fn check_commissions(
+        &mut self,
+        context: &CheckContext,
+        vote_accounts: &RpcVoteAccountStatus,
+        validator_identity_pubkeys: &[String],
+        inventory: &Inventory,
+        results: &mut CheckResults,
+    ) {
+        let CheckContext {
+            config,
+            in_maintenance,
+            ..
+        } = *context;
+        for vote_account in vote_accounts
+            .current
+            .iter()
+            .chain(vote_accounts.delinquent.iter())
+        {
+            let monitored = validator_identity_pubkeys.contains(&vote_account.node_pubkey)
+                || (config.monitor_all_current
+                    && inventory.validators().contains(&vote_account.node_pubkey));
+            // Recorded through maintenance windows, so that a change during one is not reported
+            // once it ends
+            let last_commission = if monitored {
+                self.last_commissions
+                    .insert(vote_account.vote_pubkey.clone(), vote_account.commission)
+            } else {
+                None
+            };
+            if in_maintenance.contains(&vote_account.node_pubkey) {
+                continue;
+            }
+            if let Some(last_commission) = last_commission {
+                if last_commission != vote_account.commission {
+                    *results
+                        .affected_validators
+                        .entry("commission-changed")
+                        .or_default() += 1;
+                    results.failures.push((
+                        "commission-changed",
+                        format!(
+                            "{} vote account {} commission changed from {}% to {}%",
+                            config.validator_name(&vote_account.node_pubkey),
+                            vote_account.vote_pubkey,
+                            last_commission,
+                            vote_account.commission
+                        ),
+                    ));
+                }
+            }
+            let max_commission = config
+                .validator_max_commissions
+                .get(&vote_account.node_pubkey)
+                .cloned()
+                .or(if monitored {
+                    config.max_commission
+                } else {
+                    None
+                });
+            if let Some(max_commission) = max_commission {
+                if vote_account.commission > max_commission {
+                    *results
+                        .affected_validators
+                        .entry("commission-too-high")
+                        .or_default() += 1;
+                    results.failures.push((
+                        "commission-too-high",
+                        format!(
+                            "{} commission is {}%, above {}%",
+                            config.validator_name(&vote_account.node_pubkey),
+                            vote_account.commission,
+                            max_commission
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Notify the deactivation of the `--monitor-stake-account` stake accounts
+    pub fn check_stake_accounts(&mut self, context: &CheckContext) {
+        let config = context.config;
+        if config.monitored_stake_accounts.is_empty() {
+            return;
+        }
+        match get_stake_activations(context.rpc_client, &config.monitored_stake_accounts) {
+            Ok(stake_activations) => {
+                for (stake_account, delegation, activation) in stake_activations {
+                    let last_activation = self
+                        .last_stake_activations
+                        .insert(stake_account, activation);
+                    // Only a change from the activation first seen is unexpected
+                    if last_activation.is_none() || last_activation == Some(activation) {
+                        continue;
+                    }
+                    let (check, state) = match activation {
+                        StakeActivation::Active => continue,
+                        StakeActivation::Deactivating => ("stake-deactivating", "is deactivating"),
+                        StakeActivation::Deactivated => ("stake-deactivated", "has deactivated"),
+                    };
+                    if !config.disabled_checks.contains(check) {
+                        let msg = format!(
+                            "Stake account {} delegated to {} {}: {} SOL",
+                            stake_account,
+                            delegation.voter_pubkey,
+                            state,
+                            lamports_to_sol(delegation.stake)
+                        );
+                        warn!("{}", msg);
+                        context.notify_info(&msg);
+                    }
+                }
+            }
+            Err(err) => warn!("Failed to get stake accounts: {:?}", err),
+        }
+    }
+}
+
+/// Replace the inventory of `--monitor-all-current` with the current validators, notifying the
+/// validators that left
+fn update_inventory(
+    context: &CheckContext,
+    vote_accounts: &RpcVoteAccountStatus,
+    inventory: &mut Inventory,
+) {
+    let current: BTreeSet<_> = vote_accounts
+        .current
+        .iter()
+        .map(|vote_account| vote_account.node_pubkey.clone())
+        .collect();
+    let baseline = inventory.validators().is_empty();
+    let (joined, departed) = inventory.update(current);
+    if baseline {
+        info!(
+            "Monitoring {} current validators",
+            inventory.validators().len()
+        );
+    } else {
+        for validator in joined {
+            info!("{} joined the current validator set", validator);
+        }
+        if !departed.is_empty()
+            && !context
+                .config
+                .disabled_checks
+                .contains("validator-departed")
+        {
+            let msg = format!("{} left the current validator set", departed.join(", "));
+            warn!("{}", msg);
+            context.notify_info(&msg);
+        }
+    }
+}
+
+/// Returns the stake delegated to delinquent vote accounts that was activated within the last
+/// `grace_epochs` epochs.  `new_stakes` caches the newly activated stake of every vote account
+/// for an epoch, as the effective stake only changes at epoch boundaries
+fn get_new_delinquent_stake(
+    rpc_client: &RpcClient,
+    thread_pool: &ThreadPool,
+    rpc_retry: RpcRetry,
+    vote_accounts: &RpcVoteAccountStatus,
+    grace_epochs: u64,
+    new_stakes: &mut Option<(Epoch, HashMap<Pubkey, u64>)>,
+) -> ClientResult<u64> {
+    let epoch = rpc_retry
+        .call("Epoch info", || rpc_client.get_epoch_info())?
+        .epoch;
+    let cached_epoch = new_stakes.as_ref().map(|(cached_epoch, _)| *cached_epoch);
+    if cached_epoch != Some(epoch) {
+        *new_stakes = Some((
+            epoch,
+            get_new_stakes(rpc_client, thread_pool, rpc_retry, epoch, grace_epochs)?,
+        ));
+    }
+    let (_, new_stakes) = new_stakes.as_ref().unwrap();
+
+    Ok(vote_accounts
+        .delinquent
+        .iter()
+        .filter_map(|vote_account| Pubkey::from_str(&vote_account.vote_pubkey).ok())
+        .filter_map(|vote_pubkey| new_stakes.get(&vote_pubkey))
+        .sum())
+}
+
+/// Returns the effective stake at `epoch` of the delegations activated within the last
+/// `grace_epochs` epochs, by vote account
+fn get_new_stakes(
+    rpc_client: &RpcClient,
+    thread_pool: &ThreadPool,
+    rpc_retry: RpcRetry,
+    epoch: Epoch,
+    grace_epochs: u64,
+) -> ClientResult<HashMap<Pubkey, u64>> {
+    let (stake_history, stake_accounts) = thread_pool.join(
+        || {
+            rpc_retry.call("Stake history", || {
+                rpc_client.get_account(&stake_history::id())
+            })
+        },
+        || {
+            rpc_retry.call("Stake accounts", || {
+                rpc_client.get_program_accounts(&solana_stake_program::id())
+            })
+        },
+    );
+    let stake_history = StakeHistory::from_account(&stake_history?).unwrap_or_default();
+
+    let mut new_stakes = HashMap::new();
+    for delegation in stake_accounts?
+        .into_iter()
+        .filter_map(|(_, account)| StakeState::delegation_from(&account))
+        .filter(|delegation| {
+            !delegation.is_bootstrap()
+                && epoch.saturating_sub(delegation.activation_epoch) <= grace_epochs
+        })
+    {
+        // Stake that is still warming up is not part of the delinquent stake yet
+        *new_stakes.entry(delegation.voter_pubkey).or_default() +=
+            delegation.stake(epoch, Some(&stake_history));
+    }
+    Ok(new_stakes)
+}
+
+/// Returns the identity, root slot and distance to `slot` of each current validator of
+/// `validator_identity_pubkeys` whose root slot is more than `max_root_distance` slots behind
+fn get_root_lags<'a>(
+    vote_accounts: &'a RpcVoteAccountStatus,
+    validator_identity_pubkeys: &[String],
+    slot: Slot,
+    max_root_distance: u64,
+) -> Vec<(&'a str, Slot, u64)> {
+    vote_accounts
+        .current
+        .iter()
+        .filter(|vai| validator_identity_pubkeys.contains(&vai.node_pubkey))
+        .filter_map(|vai| {
+            let root_distance = slot.saturating_sub(vai.root_slot);
+            if root_distance > max_root_distance {
+                Some((vai.node_pubkey.as_str(), vai.root_slot, root_distance))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Records the identity of each vote account used by a monitored validator, returning a message for
+/// each vote account whose identity changed since the previous interval
+fn get_identity_rotations(
+    vote_accounts: &RpcVoteAccountStatus,
+    validator_identity_pubkeys: &[String],
+    vote_account_identities: &mut HashMap<String, String>,
+) -> Vec<String> {
+    let mut rotations = vec![];
+    for vote_account in vote_accounts
+        .current
+        .iter()
+        .chain(vote_accounts.delinquent.iter())
+    {
+        if !validator_identity_pubkeys.contains(&vote_account.node_pubkey)
+            && !vote_account_identities.contains_key(&vote_account.vote_pubkey)
+        {
+            continue;
+        }
+        if let Some(last_identity) = vote_account_identities.insert(
+            vote_account.vote_pubkey.clone(),
+            vote_account.node_pubkey.clone(),
+        ) {
+            if last_identity != vote_account.node_pubkey {
+                rotations.push(format!(
+                    "Vote account {} identity rotated: {} -> {}",
+                    vote_account.vote_pubkey, last_identity, vote_account.node_pubkey
+                ));
+            }
+        }
+    }
+    rotations
+}
+
+fn get_stake_activation(
+    delegation: &Delegation,
+    epoch: Epoch,
+    stake_history: &StakeHistory,
+) -> StakeActivation {
+    if delegation.deactivation_epoch == std::u64::MAX {
+        StakeActivation::Active
+    } else if delegation.stake(epoch, Some(stake_history)) > 0 {
+        StakeActivation::Deactivating
+    } else {
+        StakeActivation::Deactivated
+    }
+}
+
+/// Returns the delegation and its activation for each of the delegated `stake_accounts`
+fn get_stake_activations(
+    rpc_client: &RpcClient,
+    stake_accounts: &[Pubkey],
+) -> ClientResult<Vec<(Pubkey, Delegation, StakeActivation)>> {
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let stake_history = StakeHistory::from_account(&rpc_client.get_account(&stake_history::id())?)
+        .unwrap_or_default();
+
+    Ok(stake_accounts
+        .iter()
+        .filter_map(
+            |stake_account| match rpc_client.get_account(stake_account) {
+                Ok(account) => {
+                    let delegation = StakeState::delegation_from(&account)?;
+                    let activation = get_stake_activation(&delegation, epoch, &stake_history);
+                    Some((*stake_account, delegation, activation))
+                }
+                Err(err) => {
+                    warn!("Failed to get stake account {}: {:?}", stake_account, err);
+                    None
+                }
+            },
+        )
+        .collect())
+}
+
+/// Returns the smallest number of validators that together hold more than a third of the
+/// stake, enough to halt the cluster
+fn get_superminority(stakes: impl Iterator<Item = u64>) -> usize {
+    let mut stakes: Vec<_> = stakes.collect();
+    stakes.sort_unstable_by(|a, b| b.cmp(a));
+    let total_stake: u128 = stakes.iter().map(|stake| *stake as u128).sum();
+    let mut cumulative_stake = 0u128;
+    for (i, stake) in stakes.iter().enumerate() {
+        cumulative_stake += *stake as u128;
+        if cumulative_stake * 3 > total_stake {
+            return i + 1;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::ThreadPoolBuilder;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use std::time::Duration;
+
+    #[test]
+    fn test_get_new_delinquent_stake_cached() {
+        let no_retry = RpcRetry {
+            retries: 0,
+            delay: Duration::from_secs(0),
+        };
+        let thread_pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetEpochInfo,
+            json!({ "epoch": 1, "slotIndex": 10, "slotsInEpoch": 100, "absoluteSlot": 110 }),
+        );
+        let rpc_client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let (vote_a, vote_b) = (Pubkey::new_rand(), Pubkey::new_rand());
+        let vote_account = |vote_pubkey: &Pubkey| {
+            json!({
+                "votePubkey": vote_pubkey.to_string(),
+                "nodePubkey": Pubkey::new_rand().to_string(),
+                "activatedStake": 42,
+                "commission": 0,
+                "epochVoteAccount": true,
+                "epochCredits": [],
+                "lastVote": 0,
+                "rootSlot": 0,
+            })
+        };
+        let vote_accounts: RpcVoteAccountStatus = serde_json::from_value(json!({
+            "current": [vote_account(&vote_a)],
+            "delinquent": [vote_account(&vote_b)],
+        }))
+        .unwrap();
+
+        // The stake accounts are not fetched again within the epoch
+        let mut new_stakes = Some((1, vec![(vote_a, 7), (vote_b, 5)].into_iter().collect()));
+        assert_eq!(
+            get_new_delinquent_stake(
+                &rpc_client,
+                &thread_pool,
+                no_retry,
+                &vote_accounts,
+                1,
+                &mut new_stakes
+            )
+            .unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_get_root_lags() {
+        let vote_account = |node_pubkey: &str, root_slot: Slot| {
+            json!({
+                "votePubkey": format!("vote-{}", node_pubkey),
+                "nodePubkey": node_pubkey,
+                "activatedStake": 42,
+                "commission": 0,
+                "epochVoteAccount": true,
+                "epochCredits": [],
+                "lastVote": 0,
+                "rootSlot": root_slot,
+            })
+        };
+        let vote_accounts: RpcVoteAccountStatus = serde_json::from_value(json!({
+            "current": [
+                vote_account("identity-a", 90),
+                vote_account("identity-b", 50),
+                vote_account("identity-c", 10),
+            ],
+            "delinquent": [vote_account("identity-d", 0)],
+        }))
+        .unwrap();
+        let identities = vec![
+            "identity-a".to_string(),
+            "identity-b".to_string(),
+            "identity-d".to_string(),
+        ];
+
+        assert_eq!(
+            get_root_lags(&vote_accounts, &identities, 100, 32),
+            vec![("identity-b", 50, 50)]
+        );
+        assert!(get_root_lags(&vote_accounts, &identities, 100, 50).is_empty());
+    }
+
+    #[test]
+    fn test_delinquent_counts() {
+        let mut delinquent_counts = DelinquentCounts::default();
+        assert!(!delinquent_counts.is_reported("a", 1));
+
+        delinquent_counts.update(vec!["a"]);
+        assert!(delinquent_counts.is_reported("a", 1));
+        assert!(!delinquent_counts.is_reported("a", 3));
+        assert!(!delinquent_counts.is_reported("b", 1));
+
+        delinquent_counts.update(vec!["a", "b"]);
+        delinquent_counts.update(vec!["a", "b"]);
+        assert!(delinquent_counts.is_reported("a", 3));
+        assert!(!delinquent_counts.is_reported("b", 3));
+
+        // A validator that is current again starts over
+        delinquent_counts.update(vec!["b"]);
+        assert!(!delinquent_counts.is_reported("a", 1));
+        assert!(delinquent_counts.is_reported("b", 3));
+        delinquent_counts.update(vec!["a"]);
+        assert!(delinquent_counts.is_reported("a", 1));
+        assert!(!delinquent_counts.is_reported("a", 2));
+        assert!(!delinquent_counts.is_reported("b", 1));
+    }
+
+    #[test]
+    fn test_get_superminority() {
+        assert_eq!(get_superminority(vec![].into_iter()), 0);
+        assert_eq!(get_superminority(vec![0, 0].into_iter()), 0);
+        assert_eq!(get_superminority(vec![10].into_iter()), 1);
+        // Exactly a third is not enough to halt the cluster
+        assert_eq!(get_superminority(vec![10, 10, 10].into_iter()), 2);
+        // The largest stakes are counted first, whatever their order
+        assert_eq!(get_superminority(vec![1, 1, 1, 1, 1, 5].into_iter()), 1);
+        assert_eq!(
+            get_superminority(vec![1, 3, 1, 2, 1, 2, 1, 1].into_iter()),
+            2
+        );
+        assert_eq!(
+            get_superminority(vec![std::u64::MAX, std::u64::MAX, std::u64::MAX].into_iter()),
+            2
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! State that is persisted to the `--state-file` so that it survives a restart
+use crate::notifier::OpenIncidents;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::*;
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    clock::{Epoch, Slot},
+    epoch_schedule::EpochSchedule,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// Epoch schedule observed by the last `--monitor-epoch-schedule` check
+    pub epoch_schedule: Option<EpochSchedule>,
+    /// UTC date the last `--opening-message-at` message was sent
+    pub last_opening_message_date: Option<NaiveDate>,
+    /// `--notify-at-slot` milestones that have already been notified
+    pub reached_slots: BTreeSet<Slot>,
+    /// `--notify-at-epoch` milestones that have already been notified
+    pub reached_epochs: BTreeSet<Epoch>,
+    /// Failure and notification state as of the last completed interval
+    pub alert: AlertState,
+}
+
+/// The state of the failures being notified, so that a restart during a failure does not notify
+/// it again nor restart the grace periods
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AlertState {
+    /// The last failure notification
+    pub last_notification_msg: String,
+    /// When the ongoing failure started
+    pub failing_since: Option<DateTime<Utc>>,
+    /// When each check was last notified, for `--min-notification-interval`
+    pub last_notification_times: HashMap<String, DateTime<Utc>>,
+    /// Number of consecutive intervals each validator has been delinquent for, for
+    /// `--delinquent-grace-intervals`
+    pub delinquent_counts: HashMap<String, usize>,
+    /// Failing checks that have been notified, for `--no-duplicate-notifications`
+    pub notified_checks: BTreeSet<String>,
+    /// Number of consecutive notifications of each failing check, for
+    /// `--notification-circuit-breaker`
+    pub notification_counts: HashMap<String, usize>,
+    /// Incidents opened in the incident tracking backends and not yet resolved
+    pub open_incidents: OpenIncidents,
+}
+
+pub struct StateFile {
+    state_file: Option<PathBuf>,
+}
+
+impl StateFile {
+    pub fn new(state_file: Option<PathBuf>) -> Self {
+        Self { state_file }
+    }
+
+    /// Load the persisted state, or the default state if there is no state file yet
+    pub fn load(&self) -> State {
+        let state_file = match &self.state_file {
+            Some(state_file) => state_file,
+            None => return State::default(),
+        };
+        match fs::read_to_string(state_file) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("Ignoring invalid {}: {}", state_file.display(), err);
+                State::default()
+            }),
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Failed to read {}: {}", state_file.display(), err);
+                }
+                State::default()
+            }
+        }
+    }
+
+    pub fn save(&self, state: &State) {
+        if let Some(state_file) = &self.state_file {
+            if let Err(err) = write_state_file(state_file, state) {
+                warn!("Failed to write {}: {}", state_file.display(), err);
+            }
+        }
+    }
+}
+
+fn write_state_file(state_file: &Path, state: &State) -> io::Result<()> {
+    let contents = serde_json::to_string_pretty(state)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+    // Write to a temporary file first so that a crash never leaves a truncated state file behind
+    let tmp_file = state_file.with_extension("tmp");
+    fs::write(&tmp_file, contents)?;
+    fs::rename(&tmp_file, state_file)
+}
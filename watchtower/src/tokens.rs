@@ -0,0 +1,119 @@
+//! Checks of the `--monitor-token-mint` supplies and `--monitor-token-account` balances, which
+//! fail when an amount changes by more than `--max-token-change-percent` in an interval
+use crate::{percent_change, CheckContext, CheckResults};
+use log::*;
+use solana_client::{client_error::Result as ClientResult, rpc_response::RpcTokenAmount};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// What the token checks remember of the previous interval
+#[derive(Default)]
+pub struct TokenChecks {
+    last_token_amounts: HashMap<Pubkey, u64>,
+}
+
+impl TokenChecks {
+    pub fn check(&mut self, context: &CheckContext, results: &mut CheckResults) {
+        let CheckContext {
+            config, rpc_client, ..
+        } = *context;
+        check_token_amounts(
+            "token-supply",
+            &config.monitored_token_mints,
+            |mint| rpc_client.get_token_supply(mint),
+            config.max_token_change_percent,
+            &mut self.last_token_amounts,
+            &mut results.failures,
+        );
+        check_token_amounts(
+            "token-account-balance",
+            &config.monitored_token_accounts,
+            |pubkey| rpc_client.get_token_account_balance(pubkey),
+            config.max_token_change_percent,
+            &mut self.last_token_amounts,
+            &mut results.failures,
+        );
+    }
+}
+
+/// Push a `test_name` failure for each token amount that changed by more than
+/// `max_change_percent` since the previous interval
+fn check_token_amounts<F>(
+    test_name: &'static str,
+    pubkeys: &[Pubkey],
+    get_token_amount: F,
+    max_change_percent: f64,
+    last_token_amounts: &mut HashMap<Pubkey, u64>,
+    failures: &mut Vec<(&'static str, String)>,
+) where
+    F: Fn(&Pubkey) -> ClientResult<RpcTokenAmount>,
+{
+    for pubkey in pubkeys {
+        match get_token_amount(pubkey) {
+            Ok(token_amount) => {
+                // Keep the last amount, a stand-in would look like a change
+                let amount = match token_amount.amount.parse::<u64>() {
+                    Ok(amount) => amount,
+                    Err(err) => {
+                        warn!(
+                            "Invalid token amount of {}: '{}': {}",
+                            pubkey, token_amount.amount, err
+                        );
+                        continue;
+                    }
+                };
+                if let Some(last_amount) = last_token_amounts.insert(*pubkey, amount) {
+                    let change_percent = percent_change(last_amount, amount);
+                    if change_percent > max_change_percent {
+                        failures.push((
+                            test_name,
+                            format!(
+                                "{} changed by {:.1}% to {}",
+                                pubkey, change_percent, token_amount.ui_amount
+                            ),
+                        ));
+                    }
+                }
+            }
+            Err(err) => warn!("Failed to get token amount of {}: {:?}", pubkey, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_token_amounts() {
+        let pubkey = Pubkey::new_rand();
+        let token_amount = |amount: &str| RpcTokenAmount {
+            ui_amount: 0.,
+            decimals: 0,
+            amount: amount.to_string(),
+        };
+        let mut last_token_amounts = HashMap::new();
+        let mut failures = vec![];
+        let mut check = |amount: &str, failures: &mut Vec<_>| {
+            check_token_amounts(
+                "token-supply",
+                &[pubkey],
+                |_| Ok(token_amount(amount)),
+                10.,
+                &mut last_token_amounts,
+                failures,
+            )
+        };
+
+        check("1000", &mut failures);
+        check("1050", &mut failures);
+        assert!(failures.is_empty());
+        // An amount that can't be parsed is skipped rather than taken as a change
+        check("18446744073709551616", &mut failures);
+        check("1050", &mut failures);
+        assert!(failures.is_empty());
+        check("2000", &mut failures);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "token-supply");
+    }
+}
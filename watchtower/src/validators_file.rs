@@ -0,0 +1,62 @@
+//! Per-validator settings loaded from the `--validators-file`
+use serde_derive::Deserialize;
+use solana_clap_utils::input_validators::is_pubkey;
+use std::{fs, io};
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorConfig {
+    /// Identity pubkey of the validator
+    pub identity: String,
+    /// Friendly name used in place of the identity in failure messages
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Overrides `--minimum-validator-balance` for this validator
+    #[serde(default)]
+    pub minimum_balance: Option<f64>,
+    /// Whether to report the validator when it is delinquent or missing
+    #[serde(default = "default_alert_on_delinquency")]
+    pub alert_on_delinquency: bool,
+    /// Only monitor the validator on the `--cluster` with this label
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+fn default_alert_on_delinquency() -> bool {
+    true
+}
+
+impl ValidatorConfig {
+    /// The settings of a validator given by `--validator-identity`
+    pub fn new(identity: String) -> Self {
+        Self {
+            identity,
+            name: None,
+            minimum_balance: None,
+            alert_on_delinquency: default_alert_on_delinquency(),
+            cluster: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ValidatorsFile {
+    validators: Vec<ValidatorConfig>,
+}
+
+/// Load the `[[validators]]` tables of a TOML validators file
+pub fn load(validators_file: &str) -> Result<Vec<ValidatorConfig>, io::Error> {
+    let contents = fs::read_to_string(validators_file)?;
+    let ValidatorsFile { validators } = toml::from_str(&contents)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{}", err)))?;
+    for validator in validators.iter() {
+        is_pubkey(validator.identity.clone()).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("invalid identity {}: {}", validator.identity, err),
+            )
+        })?;
+    }
+    Ok(validators)
+}
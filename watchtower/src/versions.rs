@@ -0,0 +1,227 @@
+//! Checks of the versions run by the cluster nodes: `--monitor-validator-version` fails when a
+//! monitored validator is behind the cluster majority, and `--minimum-cluster-version` when the
+//! cluster majority is older than the minimum version
+use crate::{CheckContext, CheckResults};
+use log::*;
+use solana_client::{
+    client_error::Result as ClientResult, rpc_client::RpcClient, rpc_response::RpcContactInfo,
+};
+use std::collections::HashMap;
+
+/// What the version checks remember of the previous intervals
+#[derive(Default)]
+pub struct VersionChecks {
+    /// Whether it has been logged that the RPC node doesn't report the cluster node versions
+    unknown_versions_logged: bool,
+}
+
+impl VersionChecks {
+    /// Check the versions run by the `monitored_identities` validators and the cluster
+    pub fn check(
+        &mut self,
+        context: &CheckContext,
+        monitored_identities: &[String],
+        results: &mut CheckResults,
+    ) {
+        let config = context.config;
+        if !config.monitor_validator_version && config.minimum_cluster_version.is_none() {
+            return;
+        }
+        let (node_count, node_versions) = match get_node_versions(context.rpc_client) {
+            Ok(node_versions) => node_versions,
+            Err(err) => {
+                warn!("Failed to get cluster nodes: {:?}", err);
+                return;
+            }
+        };
+        let majority_version = match get_majority_version(&node_versions, node_count) {
+            Some(majority_version) => majority_version,
+            None => {
+                if !self.unknown_versions_logged {
+                    warn!(
+                        "The RPC node reports the versions of only {} of {} cluster nodes",
+                        node_versions.len(),
+                        node_count
+                    );
+                    self.unknown_versions_logged = true;
+                }
+                return;
+            }
+        };
+
+        if config.monitor_validator_version {
+            let mut errors = vec![];
+            for validator_identity in monitored_identities.iter() {
+                match node_versions.get(validator_identity) {
+                    Some(version) if version < majority_version => {
+                        errors.push(format!(
+                            "{} is running {} while the cluster majority is running {}",
+                            config.validator_name(validator_identity),
+                            version,
+                            majority_version
+                        ));
+                    }
+                    Some(_) => {}
+                    None => debug!("Version of {} is unknown", validator_identity),
+                }
+            }
+            if !errors.is_empty() {
+                results
+                    .affected_validators
+                    .insert("validator-version-behind", errors.len());
+                results
+                    .failures
+                    .push(("validator-version-behind", errors.join(", ")));
+            }
+        }
+
+        if let Some(minimum_cluster_version) = &config.minimum_cluster_version {
+            if let Some(failure) =
+                cluster_version_failure(majority_version, minimum_cluster_version)
+            {
+                results.failures.push(("cluster-version", failure));
+            }
+        }
+    }
+}
+
+/// Parse the semantic version at the start of a node's version string, ignoring any suffix such
+/// as the commit
+fn parse_node_version(version: &str) -> Option<semver::Version> {
+    version
+        .split_whitespace()
+        .next()
+        .and_then(|version| semver::Version::parse(version).ok())
+}
+
+/// Returns the number of cluster nodes, and the version reported by each of them keyed by node
+/// identity
+fn get_node_versions(
+    rpc_client: &RpcClient,
+) -> ClientResult<(usize, HashMap<String, semver::Version>)> {
+    Ok(node_versions(rpc_client.get_cluster_nodes()?))
+}
+
+fn node_versions(nodes: Vec<RpcContactInfo>) -> (usize, HashMap<String, semver::Version>) {
+    let node_count = nodes.len();
+    let node_versions = nodes
+        .into_iter()
+        .filter_map(|node| {
+            let version = node.version.as_deref().and_then(parse_node_version)?;
+            Some((node.pubkey, version))
+        })
+        .collect();
+    (node_count, node_versions)
+}
+
+/// Returns the version run by the most nodes, unless the versions of most of the `node_count`
+/// cluster nodes are unknown
+fn get_majority_version(
+    node_versions: &HashMap<String, semver::Version>,
+    node_count: usize,
+) -> Option<&semver::Version> {
+    if node_versions.len() * 2 <= node_count {
+        return None;
+    }
+    let mut version_counts: HashMap<&semver::Version, usize> = HashMap::new();
+    for version in node_versions.values() {
+        *version_counts.entry(version).or_default() += 1;
+    }
+    version_counts
+        .into_iter()
+        .max_by(|(version_a, count_a), (version_b, count_b)| {
+            count_a.cmp(count_b).then(version_a.cmp(version_b))
+        })
+        .map(|(version, _)| version)
+}
+
+/// Returns the `cluster-version` failure if the cluster majority is running an older version
+/// than --minimum-cluster-version
+fn cluster_version_failure(
+    majority_version: &semver::Version,
+    minimum_cluster_version: &semver::Version,
+) -> Option<String> {
+    if majority_version < minimum_cluster_version {
+        Some(format!(
+            "The cluster majority is running {}, older than the minimum version {}",
+            majority_version, minimum_cluster_version
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_majority_version() {
+        let version = |version| semver::Version::parse(version).unwrap();
+        let mut node_versions = HashMap::new();
+        assert_eq!(get_majority_version(&node_versions, 0), None);
+
+        node_versions.insert("a".to_string(), version("1.9.0"));
+        node_versions.insert("b".to_string(), version("1.10.0"));
+        // A tie goes to the newest version, compared as semantic versions
+        assert_eq!(
+            get_majority_version(&node_versions, 2),
+            Some(&version("1.10.0"))
+        );
+        // Unknown unless most of the cluster nodes report their version
+        assert_eq!(get_majority_version(&node_versions, 4), None);
+
+        node_versions.insert("c".to_string(), version("1.9.0"));
+        let majority_version = get_majority_version(&node_versions, 3).unwrap();
+        assert_eq!(majority_version, &version("1.9.0"));
+        assert!(majority_version < &version("1.10.0"));
+    }
+
+    #[test]
+    fn test_cluster_version_failure() {
+        let node = |pubkey: &str, version: Option<&str>| RpcContactInfo {
+            pubkey: pubkey.to_string(),
+            gossip: None,
+            tpu: None,
+            rpc: None,
+            version: version.map(|version| version.to_string()),
+        };
+        let minimum_cluster_version = semver::Version::parse("1.1.0").unwrap();
+
+        let (node_count, node_versions) = node_versions(vec![
+            node("a", Some("1.0.22 6fbc5f9e")),
+            node("b", Some("1.0.22")),
+            node("c", Some("1.1.1")),
+            node("d", None),
+        ]);
+        assert_eq!(node_count, 4);
+        assert_eq!(node_versions.len(), 3);
+        let majority_version = get_majority_version(&node_versions, node_count).unwrap();
+        assert_eq!(
+            cluster_version_failure(majority_version, &minimum_cluster_version),
+            Some(
+                "The cluster majority is running 1.0.22, older than the minimum version 1.1.0"
+                    .to_string()
+            )
+        );
+
+        let (node_count, node_versions) = node_versions(vec![
+            node("a", Some("1.1.0")),
+            node("b", Some("1.1.1")),
+            node("c", Some("1.1.1")),
+        ]);
+        let majority_version = get_majority_version(&node_versions, node_count).unwrap();
+        assert_eq!(
+            cluster_version_failure(majority_version, &minimum_cluster_version),
+            None
+        );
+
+        // Only the version of the RPC node itself is known
+        let (node_count, node_versions) = node_versions(vec![
+            node("a", Some("1.0.22")),
+            node("b", None),
+            node("c", None),
+        ]);
+        assert_eq!(get_majority_version(&node_versions, node_count), None);
+    }
+}